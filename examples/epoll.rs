@@ -24,6 +24,13 @@ fn main() {
 
     while let Ok(num) = epfd.wait(events, EpollTimeout::NONE) {
         for ev in &events[..num] {
+            // Reported without being requested once the trigger is
+            // released, e.g. because a watched cgroup was removed
+            if ev.events().contains(EpollFlags::EPOLLERR) {
+                eprintln!("trigger released: {ev:?}");
+                epfd.delete(psi_fd.as_fd()).unwrap();
+                return;
+            }
             eprintln!("event occurred: {ev:?}")
         }
     }
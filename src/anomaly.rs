@@ -0,0 +1,183 @@
+/// Online detector for stall ratios that deviate from a learned baseline.
+///
+/// The baseline is an exponentially weighted moving mean and variance of
+/// the observed ratios, so it adapts to each host instead of relying on a
+/// static threshold. Feed it one detector per entry, e.g. with ratios
+/// derived from [`PsiFd::read_stats`](crate::PsiFd::read_stats).
+///
+/// ```
+/// # use presutaoru::*;
+/// let mut detector = AnomalyDetector::new();
+///
+/// for ratio in [2.0, 2.5, 1.5, 2.0, 2.2, 1.8, 2.1, 1.9, 2.0, 2.3] {
+///     assert!(detector.observe(ratio).is_none());
+/// }
+/// let anomaly = detector.observe(40.0).unwrap();
+/// assert!(anomaly.score > 3.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnomalyDetector {
+    alpha: f32,
+    threshold: f32,
+    warmup: u32,
+    seen: u32,
+    mean: f32,
+    variance: f32,
+}
+
+/// A sample reported by [`AnomalyDetector::observe`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anomaly {
+    /// Deviation from the baseline in standard deviations.
+    ///
+    /// Negative when pressure dropped below the baseline.
+    pub score: f32,
+    /// Baseline stall ratio in percent before the sample was learned.
+    pub baseline: f32,
+}
+
+impl AnomalyDetector {
+    /// Standard deviations are floored to this many percentage points,
+    /// so that an idle baseline does not turn every blip into an anomaly.
+    pub const MIN_DEVIATION: f32 = 0.5;
+
+    /// Creates a detector with a smoothing factor of 0.1, a threshold of
+    /// 3 standard deviations and a warmup of 10 samples.
+    pub fn new() -> Self {
+        Self {
+            alpha: 0.1,
+            threshold: 3.0,
+            warmup: 10,
+            seen: 0,
+            mean: 0.0,
+            variance: 0.0,
+        }
+    }
+
+    /// Sets the weight of each new sample in the baseline.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` is not within `(0, 1]`.
+    pub fn alpha(mut self, alpha: f32) -> Self {
+        assert!(alpha > 0.0 && alpha <= 1.0, "alpha must be within (0, 1]");
+        self.alpha = alpha;
+        self
+    }
+
+    /// Sets the absolute score at which a sample is reported.
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets the number of samples learned before any are reported.
+    pub fn warmup(mut self, warmup: u32) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    /// Scores a stall ratio in percent against the baseline, then
+    /// learns it.
+    ///
+    /// Returns the [`Anomaly`] if the score reaches the threshold.
+    pub fn observe(&mut self, ratio: f32) -> Option<Anomaly> {
+        if self.seen == 0 {
+            self.seen = 1;
+            self.mean = ratio;
+            return None;
+        }
+
+        let baseline = self.mean;
+        let score = (ratio - baseline) / self.variance.sqrt().max(Self::MIN_DEVIATION);
+
+        let diff = ratio - self.mean;
+        self.mean += self.alpha * diff;
+        self.variance = (1.0 - self.alpha) * (self.variance + self.alpha * diff * diff);
+        self.seen = self.seen.saturating_add(1);
+
+        (self.seen > self.warmup && score.abs() >= self.threshold)
+            .then_some(Anomaly { score, baseline })
+    }
+
+    /// Returns the current baseline stall ratio in percent.
+    pub fn baseline(&self) -> f32 {
+        self.mean
+    }
+
+    /// Forgets the learned baseline.
+    pub fn reset(&mut self) {
+        self.seen = 0;
+        self.mean = 0.0;
+        self.variance = 0.0;
+    }
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warmed_up() -> AnomalyDetector {
+        let mut detector = AnomalyDetector::new();
+        for ratio in [10.0, 12.0, 8.0, 11.0, 9.0, 10.0, 12.0, 8.0, 10.0, 10.0] {
+            assert!(detector.observe(ratio).is_none());
+        }
+        detector
+    }
+
+    #[test]
+    fn reports_spikes_and_drops() {
+        let mut detector = warmed_up();
+
+        let spike = detector.observe(60.0).unwrap();
+        assert!(spike.score > 3.0);
+        assert!((spike.baseline - 10.0).abs() < 1.0);
+
+        let mut detector = warmed_up();
+        assert!(detector.observe(0.0).unwrap().score < -3.0);
+    }
+
+    #[test]
+    fn ignores_normal_samples() {
+        let mut detector = warmed_up();
+
+        assert!(detector.observe(11.0).is_none());
+        assert!(detector.observe(9.0).is_none());
+    }
+
+    #[test]
+    fn nothing_reported_during_warmup() {
+        let mut detector = AnomalyDetector::new().warmup(3);
+
+        assert!(detector.observe(0.0).is_none());
+        assert!(detector.observe(50.0).is_none());
+        assert!(detector.observe(0.0).is_none());
+        assert!(detector.observe(100.0).is_some());
+    }
+
+    #[test]
+    fn idle_baseline_tolerates_blips() {
+        let mut detector = AnomalyDetector::new().warmup(0);
+        for _ in 0..20 {
+            detector.observe(0.0);
+        }
+
+        assert!(detector.observe(1.0).is_none());
+        assert!(detector.observe(5.0).is_some());
+    }
+
+    #[test]
+    fn reset_forgets_baseline() {
+        let mut detector = warmed_up();
+        detector.reset();
+
+        assert!(detector.observe(60.0).is_none());
+        assert_eq!(detector.baseline(), 60.0);
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    io,
+    os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd},
+};
+
+use tokio::io::{Interest, unix::AsyncFd};
+
+use crate::{PsiFd, PsiStats};
+
+/// A [`PsiFd`] registered with the tokio reactor.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use presutaoru::*;
+/// # async fn run() -> std::io::Result<()> {
+/// let psi_fd = PsiFd::builder()
+///     .entry(PsiEntry::Global(GlobalEntryType::Memory))
+///     .stall_type(StallType::Some)
+///     .stall_amount(Duration::from_millis(150))
+///     .time_window(Duration::from_secs(1))
+///     .build()
+///     .unwrap();
+///
+/// let async_fd = PsiAsyncFd::new(psi_fd)?;
+///
+/// loop {
+///     let stats = async_fd.next_stats().await?;
+///     eprintln!("{:?}", stats.some);
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PsiAsyncFd {
+    inner: AsyncFd<PsiFd>,
+}
+
+impl PsiAsyncFd {
+    /// Registers the [`PsiFd`] with the current tokio reactor.
+    ///
+    /// # Panics
+    /// Panics when called outside of a tokio runtime.
+    pub fn new(fd: PsiFd) -> io::Result<Self> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(fd = fd.as_raw_fd(), "registering psi fd with tokio");
+        Ok(Self {
+            inner: AsyncFd::with_interest(fd, Interest::PRIORITY | Interest::ERROR)?,
+        })
+    }
+
+    /// Waits until the trigger fires.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::NotFound`] once the
+    /// kernel has released the trigger, which happens when the watched
+    /// cgroup is removed. The fd will not fire again and should be
+    /// dropped.
+    pub async fn next_event(&self) -> io::Result<()> {
+        let mut guard = self
+            .inner
+            .ready(Interest::PRIORITY | Interest::ERROR)
+            .await?;
+        // A released trigger reports POLLERR alongside POLLPRI
+        if guard.ready().is_error() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(fd = self.as_raw_fd(), "psi trigger was released");
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "psi trigger was released",
+            ));
+        }
+        guard.clear_ready();
+        #[cfg(feature = "tracing")]
+        tracing::trace!(fd = self.as_raw_fd(), "psi trigger fired");
+        Ok(())
+    }
+
+    /// Waits until the trigger fires and reads the averages and totals
+    /// at that moment.
+    ///
+    /// The stats are read through the already open fd, so they always
+    /// describe the file that fired. Errors are reported as by
+    /// [`PsiAsyncFd::next_event`] and [`PsiFd::read_stats`].
+    pub async fn next_stats(&self) -> io::Result<PsiStats> {
+        self.next_event().await?;
+        self.inner.get_ref().read_stats()
+    }
+
+    /// Reads the current contents of the PSI file.
+    ///
+    /// See [`PsiFd::read_stats`].
+    pub async fn read_stats(&self) -> io::Result<PsiStats> {
+        self.inner.get_ref().read_stats()
+    }
+
+    /// Returns a shared reference to the inner [`PsiFd`].
+    pub fn get_ref(&self) -> &PsiFd {
+        self.inner.get_ref()
+    }
+
+    /// Deregisters the fd from the reactor and returns the inner [`PsiFd`].
+    pub fn into_inner(self) -> PsiFd {
+        self.inner.into_inner()
+    }
+}
+
+impl AsRawFd for PsiAsyncFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl AsFd for PsiAsyncFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.get_ref().as_fd()
+    }
+}
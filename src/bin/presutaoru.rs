@@ -0,0 +1,620 @@
+//! Registers PSI triggers from the command line and prints their events,
+//! or checks the current pressure against a threshold.
+//!
+//! ```console
+//! $ presutaoru --entry memory --some 150ms --window 1s --entry cpu --some 500us --window 1s
+//! $ presutaoru check memory --full --max-avg10 5.0
+//! ```
+
+use std::{
+    env,
+    fmt::Write as _,
+    os::fd::AsRawFd,
+    path::Path,
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use presutaoru::*;
+
+const USAGE: &str = "\
+usage: presutaoru [--json] [--interval <duration>] <trigger>...
+       presutaoru check <cpu|io|irq|memory|path> (--some|--full) --max-avg10 <percent>
+                        [--format <text|nagios>] [--critical-avg10 <percent>]
+
+Each trigger is given as
+    --entry <cpu|io|irq|memory|path> (--some|--full) <duration> --window <duration>
+
+A path names a pressure file, e.g. /sys/fs/cgroup/foo/memory.pressure.
+Durations take a us, ms or s suffix and default to microseconds.
+
+Options:
+    --json                  print events as JSON lines
+    --interval <duration>   also print the stats of every entry periodically
+
+The check subcommand reads the entry once and exits with 0 if the avg10
+of the given line is at most the maximum, 1 if it is above and 2 on
+errors. With --format nagios it prints a plugin status line with perfdata
+and exits with 0 for OK, 1 for WARNING above --max-avg10, 2 for CRITICAL
+above --critical-avg10 and 3 for UNKNOWN if the entry cannot be read.
+";
+
+struct Trigger {
+    entry: String,
+    stall: Option<(StallType, Duration)>,
+    window: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Nagios,
+}
+
+struct Check {
+    entry: String,
+    stall_type: StallType,
+    max_avg10: f32,
+    critical_avg10: Option<f32>,
+    format: Format,
+}
+
+struct Args {
+    json: bool,
+    interval: Option<Duration>,
+    triggers: Vec<Trigger>,
+}
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1).peekable();
+    if args.next_if(|arg| arg == "check").is_some() {
+        return match parse_check(args) {
+            Ok(parsed) => check(&parsed),
+            Err(e) => {
+                eprintln!("error: {e}\n\n{USAGE}");
+                ExitCode::from(2)
+            }
+        };
+    }
+
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut fds = Vec::new();
+    for trigger in &args.triggers {
+        match build(trigger) {
+            Ok(fd) => fds.push(fd),
+            Err(e) => {
+                eprintln!("error: {}: {e}", trigger.entry);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let mut deadline = args.interval.map(|interval| Instant::now() + interval);
+    let mut pollfds: Vec<libc::pollfd> = fds
+        .iter()
+        .map(|fd| libc::pollfd {
+            fd: fd.as_raw_fd(),
+            events: libc::POLLPRI,
+            revents: 0,
+        })
+        .collect();
+
+    while pollfds.iter().any(|pollfd| pollfd.fd >= 0) {
+        let timeout = deadline.map_or(-1, |deadline| {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            remaining.as_millis().min(i32::MAX as u128) as i32
+        });
+        // SAFETY:
+        // The fds outlive the call and the length matches the buffer
+        let n = unsafe { libc::poll(pollfds.as_mut_ptr(), pollfds.len() as _, timeout) };
+        if n < 0 {
+            let e = std::io::Error::last_os_error();
+            if e.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            eprintln!("error: poll: {e}");
+            return ExitCode::FAILURE;
+        }
+
+        for (pollfd, fd) in pollfds.iter_mut().zip(&fds) {
+            if pollfd.fd < 0 {
+                continue;
+            }
+            if pollfd.revents & libc::POLLERR != 0 {
+                // The trigger was released, e.g. its cgroup was removed
+                print_event(args.json, "released", fd);
+                pollfd.fd = -1;
+            } else if pollfd.revents & libc::POLLPRI != 0 {
+                print_event(args.json, "fired", fd);
+            }
+        }
+
+        // Firings must not hold back the periodic stats
+        let now = Instant::now();
+        if let (Some(deadline), Some(interval)) = (&mut deadline, args.interval) {
+            if now >= *deadline {
+                for (pollfd, fd) in pollfds.iter().zip(&fds) {
+                    if pollfd.fd >= 0 {
+                        print_event(args.json, "stats", fd);
+                    }
+                }
+                while *deadline <= now {
+                    *deadline += interval;
+                }
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args {
+        json: false,
+        interval: None,
+        triggers: Vec::new(),
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                std::process::exit(0);
+            }
+            "--json" => parsed.json = true,
+            "--interval" => {
+                let interval = parse_duration(&value()?)?;
+                if interval.is_zero() {
+                    return Err("--interval must not be zero".to_owned());
+                }
+                parsed.interval = Some(interval);
+            }
+            "--entry" => parsed.triggers.push(Trigger {
+                entry: value()?,
+                stall: None,
+                window: None,
+            }),
+            "--some" | "--full" | "--window" => {
+                let duration = parse_duration(&value()?)?;
+                let trigger = parsed
+                    .triggers
+                    .last_mut()
+                    .ok_or_else(|| format!("{arg} before --entry"))?;
+                match arg.as_str() {
+                    "--some" => trigger.stall = Some((StallType::Some, duration)),
+                    "--full" => trigger.stall = Some((StallType::Full, duration)),
+                    _ => trigger.window = Some(duration),
+                }
+            }
+            _ => return Err(format!("unknown argument {arg}")),
+        }
+    }
+
+    if parsed.triggers.is_empty() {
+        return Err("no trigger given".to_owned());
+    }
+    Ok(parsed)
+}
+
+fn parse_check(mut args: impl Iterator<Item = String>) -> Result<Check, String> {
+    let mut entry = None;
+    let mut stall_type = None;
+    let mut max_avg10 = None;
+    let mut critical_avg10 = None;
+    let mut format = Format::Text;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                std::process::exit(0);
+            }
+            "--some" => stall_type = Some(StallType::Some),
+            "--full" => stall_type = Some(StallType::Full),
+            "--max-avg10" | "--critical-avg10" => {
+                let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+                let percent = parse_percent(&value)?;
+                if arg == "--max-avg10" {
+                    max_avg10 = Some(percent);
+                } else {
+                    critical_avg10 = Some(percent);
+                }
+            }
+            "--format" => {
+                format = match args.next().as_deref() {
+                    Some("text") => Format::Text,
+                    Some("nagios") => Format::Nagios,
+                    Some(other) => return Err(format!("unknown format {other}")),
+                    None => return Err("--format needs a value".to_owned()),
+                }
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown argument {arg}")),
+            _ if entry.is_none() => entry = Some(arg),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+
+    if critical_avg10.is_some() && format != Format::Nagios {
+        return Err("--critical-avg10 needs --format nagios".to_owned());
+    }
+    Ok(Check {
+        entry: entry.ok_or("check needs an entry")?,
+        stall_type: stall_type.ok_or("check needs --some or --full")?,
+        max_avg10: max_avg10.ok_or("check needs --max-avg10")?,
+        critical_avg10,
+        format,
+    })
+}
+
+fn parse_percent(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(percent),
+        _ => Err(format!("invalid percentage {s:?}")),
+    }
+}
+
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let (number, unit): (&str, fn(u64) -> Duration) = if let Some(us) = s.strip_suffix("us") {
+        (us, Duration::from_micros)
+    } else if let Some(ms) = s.strip_suffix("ms") {
+        (ms, Duration::from_millis)
+    } else if let Some(secs) = s.strip_suffix('s') {
+        (secs, Duration::from_secs)
+    } else {
+        (s, Duration::from_micros)
+    };
+    number
+        .parse()
+        .map(unit)
+        .map_err(|_| format!("invalid duration {s:?}"))
+}
+
+fn entry(name: &str) -> PsiEntry<'_> {
+    match name {
+        "cpu" => PsiEntry::Global(GlobalEntryType::Cpu),
+        "io" => PsiEntry::Global(GlobalEntryType::Io),
+        "irq" => PsiEntry::Global(GlobalEntryType::Irq),
+        "memory" => PsiEntry::Global(GlobalEntryType::Memory),
+        path => PsiEntry::Custom(Path::new(path)),
+    }
+}
+
+fn build(trigger: &Trigger) -> Result<PsiFd, PsiFdBuilderError> {
+    let mut builder = PsiFd::builder().entry(entry(&trigger.entry));
+    if let Some((stall_type, amount)) = trigger.stall {
+        builder = builder.stall_type(stall_type).stall_amount(amount);
+    }
+    if let Some(window) = trigger.window {
+        builder = builder.time_window(window);
+    }
+    builder.build()
+}
+
+fn check(check: &Check) -> ExitCode {
+    let line = match entry(&check.entry).read() {
+        Ok(stats) => stats
+            .get(check.stall_type)
+            .copied()
+            .ok_or_else(|| format!("no {} line", check.stall_type)),
+        Err(e) => Err(e.to_string()),
+    };
+
+    match line {
+        Ok(line) => {
+            let (out, code) = report(check, &line);
+            println!("{out}");
+            ExitCode::from(code)
+        }
+        Err(e) => match check.format {
+            Format::Text => {
+                eprintln!("error: {}: {e}", check.entry);
+                ExitCode::from(2)
+            }
+            Format::Nagios => {
+                println!("PSI UNKNOWN - {}: {e}", check.entry);
+                ExitCode::from(3)
+            }
+        },
+    }
+}
+
+/// Formats the outcome of a check and returns it with the exit code.
+fn report(check: &Check, line: &StallStats) -> (String, u8) {
+    let Check {
+        entry, stall_type, ..
+    } = check;
+    match check.format {
+        Format::Text => {
+            let exceeded = line.avg10 > check.max_avg10;
+            let out = format!(
+                "{} {entry} {stall_type} avg10={:.2} max-avg10={:.2}",
+                if exceeded { "exceeded" } else { "ok" },
+                line.avg10,
+                check.max_avg10
+            );
+            (out, exceeded as u8)
+        }
+        Format::Nagios => {
+            let (status, code) = if check
+                .critical_avg10
+                .is_some_and(|critical| line.avg10 > critical)
+            {
+                ("CRITICAL", 2)
+            } else if line.avg10 > check.max_avg10 {
+                ("WARNING", 1)
+            } else {
+                ("OK", 0)
+            };
+            let critical = check
+                .critical_avg10
+                .map(|critical| format!("{critical:.2}"))
+                .unwrap_or_default();
+            let out = format!(
+                "PSI {status} - {entry} {stall_type} avg10={:.2}% \
+                 | avg10={:.2}%;{:.2};{critical};0;100 avg60={:.2}%;;;0;100 \
+                 avg300={:.2}%;;;0;100 total={}us",
+                line.avg10,
+                line.avg10,
+                check.max_avg10,
+                line.avg60,
+                line.avg300,
+                line.total.as_micros()
+            );
+            (out, code)
+        }
+    }
+}
+
+fn print_event(json: bool, event: &str, fd: &PsiFd) {
+    let path = fd.path().unwrap_or_default();
+    let stats = match fd.read_stats() {
+        Ok(stats) => Some(stats),
+        // A released trigger may no longer be readable
+        Err(_) if event == "released" => None,
+        Err(e) => {
+            eprintln!("error: {}: {e}", path.display());
+            return;
+        }
+    };
+    let lines = stats.iter().flat_map(|stats| {
+        [("some", &stats.some), ("full", &stats.full)]
+            .into_iter()
+            .filter_map(|(name, line)| Some((name, line.as_ref()?)))
+    });
+
+    let mut out = String::new();
+    if json {
+        let _ = write!(out, r#"{{"event":"{event}","path":{}"#, json_string(&path));
+        for (name, line) in lines {
+            let _ = write!(
+                out,
+                r#","{name}":{{"avg10":{},"avg60":{},"avg300":{},"total_us":{}}}"#,
+                line.avg10,
+                line.avg60,
+                line.avg300,
+                line.total.as_micros()
+            );
+        }
+        out.push('}');
+    } else {
+        let _ = write!(out, "{event} {}", path.display());
+        for (name, line) in lines {
+            let _ = write!(
+                out,
+                " {name} avg10={:.2} avg60={:.2} avg300={:.2} total={}",
+                line.avg10,
+                line.avg60,
+                line.avg300,
+                line.total.as_micros()
+            );
+        }
+    }
+    println!("{out}");
+}
+
+fn json_string(path: &Path) -> String {
+    let mut out = String::from('"');
+    for c in path.to_string_lossy().chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn triggers() {
+        let parsed = args(&[
+            "--json", "--entry", "memory", "--some", "150ms", "--window", "1s", "--entry", "cpu",
+            "--full", "500",
+        ])
+        .unwrap();
+
+        assert!(parsed.json);
+        assert_eq!(parsed.interval, None);
+        assert_eq!(parsed.triggers.len(), 2);
+        assert_eq!(parsed.triggers[0].entry, "memory");
+        assert_eq!(
+            parsed.triggers[0].stall,
+            Some((StallType::Some, Duration::from_millis(150)))
+        );
+        assert_eq!(parsed.triggers[0].window, Some(Duration::from_secs(1)));
+        assert_eq!(parsed.triggers[1].entry, "cpu");
+        assert_eq!(
+            parsed.triggers[1].stall,
+            Some((StallType::Full, Duration::from_micros(500)))
+        );
+        assert_eq!(parsed.triggers[1].window, None);
+    }
+
+    #[test]
+    fn invalid_args() {
+        for (input, error) in [
+            (
+                &["--some", "1s", "--entry", "cpu"][..],
+                "--some before --entry",
+            ),
+            (
+                &["--window", "1s", "--entry", "cpu"],
+                "--window before --entry",
+            ),
+            (&["--entry"], "--entry needs a value"),
+            (&["--entry", "cpu", "--some"], "--some needs a value"),
+            (
+                &["--interval", "0s", "--entry", "cpu"],
+                "--interval must not be zero",
+            ),
+            (&["--entry", "cpu", "--bogus"], "unknown argument --bogus"),
+            (&["--json"], "no trigger given"),
+        ] {
+            assert_eq!(args(input).err().as_deref(), Some(error), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn check_args() {
+        let parse = |args: &[&str]| parse_check(args.iter().map(|arg| arg.to_string()));
+
+        let parsed = parse(&["memory", "--full", "--max-avg10", "5.0"]).unwrap();
+        assert_eq!(parsed.entry, "memory");
+        assert_eq!(parsed.stall_type, StallType::Full);
+        assert_eq!(parsed.max_avg10, 5.0);
+
+        let parsed = parse(&["--some", "--max-avg10", "10", "/tmp/cpu.pressure"]).unwrap();
+        assert_eq!(parsed.entry, "/tmp/cpu.pressure");
+        assert_eq!(parsed.stall_type, StallType::Some);
+
+        for (input, error) in [
+            (&["--some", "--max-avg10", "5"][..], "check needs an entry"),
+            (
+                &["memory", "--max-avg10", "5"],
+                "check needs --some or --full",
+            ),
+            (&["memory", "--some"], "check needs --max-avg10"),
+            (
+                &["memory", "--some", "--max-avg10"],
+                "--max-avg10 needs a value",
+            ),
+            (
+                &["memory", "--some", "--max-avg10", "101"],
+                r#"invalid percentage "101""#,
+            ),
+            (
+                &["memory", "--some", "--max-avg10", "x"],
+                r#"invalid percentage "x""#,
+            ),
+            (&["memory", "cpu"], "unexpected argument cpu"),
+            (&["memory", "--window", "1s"], "unknown argument --window"),
+            (
+                &["memory", "--some", "--max-avg10", "5", "--format", "xml"],
+                "unknown format xml",
+            ),
+            (
+                &[
+                    "memory",
+                    "--some",
+                    "--max-avg10",
+                    "5",
+                    "--critical-avg10",
+                    "9",
+                ],
+                "--critical-avg10 needs --format nagios",
+            ),
+        ] {
+            assert_eq!(parse(input).err().as_deref(), Some(error), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn reports() {
+        let mut check = Check {
+            entry: "memory".to_owned(),
+            stall_type: StallType::Full,
+            max_avg10: 5.0,
+            critical_avg10: None,
+            format: Format::Text,
+        };
+        let line = |avg10| StallStats {
+            avg10,
+            avg60: 1.5,
+            avg300: 0.25,
+            total: Duration::from_micros(42),
+        };
+
+        assert_eq!(
+            report(&check, &line(5.0)),
+            ("ok memory full avg10=5.00 max-avg10=5.00".to_owned(), 0)
+        );
+        assert_eq!(report(&check, &line(5.5)).1, 1);
+
+        check.format = Format::Nagios;
+        check.critical_avg10 = Some(10.0);
+        assert_eq!(
+            report(&check, &line(1.0)),
+            (
+                "PSI OK - memory full avg10=1.00% | avg10=1.00%;5.00;10.00;0;100 \
+                 avg60=1.50%;;;0;100 avg300=0.25%;;;0;100 total=42us"
+                    .to_owned(),
+                0
+            )
+        );
+        assert!(report(&check, &line(7.0)).0.starts_with("PSI WARNING - "));
+        assert_eq!(report(&check, &line(7.0)).1, 1);
+        assert!(report(&check, &line(12.0)).0.starts_with("PSI CRITICAL - "));
+        assert_eq!(report(&check, &line(12.0)).1, 2);
+
+        check.critical_avg10 = None;
+        assert_eq!(report(&check, &line(50.0)).1, 1);
+        assert!(report(&check, &line(50.0)).0.contains(";5.00;;0;100 "));
+    }
+
+    #[test]
+    fn durations() {
+        for (input, expected) in [
+            ("250", Duration::from_micros(250)),
+            ("250us", Duration::from_micros(250)),
+            ("150ms", Duration::from_millis(150)),
+            ("2s", Duration::from_secs(2)),
+        ] {
+            assert_eq!(parse_duration(input), Ok(expected), "{input}");
+        }
+
+        for input in ["", "s", "ms", "1h", "1.5s", "-1s", "x"] {
+            assert!(parse_duration(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn json_escapes() {
+        assert_eq!(
+            json_string(Path::new("/sys/fs/cgroup/a.slice/memory.pressure")),
+            r#""/sys/fs/cgroup/a.slice/memory.pressure""#
+        );
+        assert_eq!(
+            json_string(Path::new("/tmp/\"quoted\"\\back\n\t\u{7f}")),
+            r#""/tmp/\"quoted\"\\back\u000a\u0009\u007f""#
+        );
+    }
+}
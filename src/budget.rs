@@ -0,0 +1,172 @@
+use std::time::{Duration, Instant};
+
+use crate::{PsiStats, StallType};
+
+/// Accounts the stall time of one PSI file against a budget per period,
+/// e.g. 5 stall seconds per hour for a tenant's cgroup.
+///
+/// Feed it every read of the file with [`StallBudget::observe`]. The
+/// stall time consumed is taken from the growth of the `total` field,
+/// so reads may be as sparse as the caller likes. Keep one budget per
+/// monitored cgroup.
+///
+/// ```no_run
+/// # use std::{path::Path, time::{Duration, Instant}};
+/// # use presutaoru::*;
+/// let entry = PsiEntry::Cgroup(CgroupEntryType::Cpu, Path::new("/sys/fs/cgroup/tenant-a"));
+/// let mut budget = StallBudget::new(
+///     StallType::Some,
+///     Duration::from_secs(5),
+///     Duration::from_secs(3600),
+/// );
+///
+/// loop {
+///     if let Some(consumed) = budget.observe(&entry.read().unwrap(), Instant::now()) {
+///         eprintln!("{entry} stalled for {consumed:?} this hour");
+///     }
+///     std::thread::sleep(Duration::from_secs(10));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StallBudget {
+    stall_type: StallType,
+    budget: Duration,
+    period: Duration,
+    period_start: Option<Instant>,
+    consumed: Duration,
+    last_total: Option<Duration>,
+    exceeded: bool,
+}
+
+impl StallBudget {
+    /// Creates a budget allowing `budget` of stall time of the given
+    /// [`StallType`] every `period`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn new(stall_type: StallType, budget: Duration, period: Duration) -> Self {
+        assert!(!period.is_zero(), "period must not be zero");
+        Self {
+            stall_type,
+            budget,
+            period,
+            period_start: None,
+            consumed: Duration::ZERO,
+            last_total: None,
+            exceeded: false,
+        }
+    }
+
+    /// Accounts a read of the PSI file taken at `now`.
+    ///
+    /// Returns the stall time consumed in the current period the first
+    /// time it exceeds the budget, and `None` otherwise. A new period
+    /// starts once the previous one has elapsed, resetting the consumed
+    /// time. Reads lacking the line of the stall type are ignored.
+    pub fn observe(&mut self, stats: &PsiStats, now: Instant) -> Option<Duration> {
+        let total = stats.get(self.stall_type)?.total;
+
+        match self.period_start {
+            Some(start) if now.saturating_duration_since(start) < self.period => {}
+            _ => {
+                self.period_start = Some(now);
+                self.consumed = Duration::ZERO;
+                self.exceeded = false;
+            }
+        }
+
+        // A total that went backwards, e.g. on a recreated cgroup,
+        // consumed nothing
+        if let Some(last) = self.last_total {
+            self.consumed += total.saturating_sub(last);
+        }
+        self.last_total = Some(total);
+
+        if self.exceeded || self.consumed <= self.budget {
+            return None;
+        }
+        self.exceeded = true;
+        Some(self.consumed)
+    }
+
+    /// Returns the stall time consumed in the current period.
+    pub fn consumed(&self) -> Duration {
+        self.consumed
+    }
+
+    /// Returns the stall time left in the current period.
+    pub fn remaining(&self) -> Duration {
+        self.budget.saturating_sub(self.consumed)
+    }
+
+    /// Returns whether the budget of the current period is exhausted.
+    pub fn is_exceeded(&self) -> bool {
+        self.exceeded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(total_ms: u64) -> PsiStats {
+        format!(
+            "some avg10=0.00 avg60=0.00 avg300=0.00 total={}\n",
+            total_ms * 1000
+        )
+        .parse()
+        .unwrap()
+    }
+
+    const ONE_SEC: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn reports_overage_once_per_period() {
+        let mut budget = StallBudget::new(StallType::Some, ONE_SEC * 5, ONE_SEC * 3600);
+        let start = Instant::now();
+
+        assert_eq!(budget.observe(&stats(10_000), start), None);
+        assert_eq!(budget.observe(&stats(14_000), start + ONE_SEC * 10), None);
+        assert_eq!(budget.remaining(), ONE_SEC);
+
+        assert_eq!(
+            budget.observe(&stats(16_000), start + ONE_SEC * 20),
+            Some(ONE_SEC * 6)
+        );
+        assert!(budget.is_exceeded());
+        assert_eq!(budget.observe(&stats(20_000), start + ONE_SEC * 30), None);
+        assert_eq!(budget.consumed(), ONE_SEC * 10);
+    }
+
+    #[test]
+    fn new_period_resets_consumption() {
+        let mut budget = StallBudget::new(StallType::Some, ONE_SEC * 5, ONE_SEC * 60);
+        let start = Instant::now();
+
+        budget.observe(&stats(0), start);
+        assert!(
+            budget
+                .observe(&stats(6_000), start + ONE_SEC * 30)
+                .is_some()
+        );
+
+        assert_eq!(budget.observe(&stats(7_000), start + ONE_SEC * 60), None);
+        assert!(!budget.is_exceeded());
+        assert_eq!(budget.consumed(), ONE_SEC);
+    }
+
+    #[test]
+    fn ignores_missing_line_and_reset_totals() {
+        let mut budget = StallBudget::new(StallType::Full, ONE_SEC * 5, ONE_SEC * 60);
+        let start = Instant::now();
+
+        assert_eq!(budget.observe(&stats(1_000), start), None);
+        assert_eq!(budget.consumed(), Duration::ZERO);
+
+        let mut budget = StallBudget::new(StallType::Some, ONE_SEC * 5, ONE_SEC * 60);
+        budget.observe(&stats(9_000), start);
+        budget.observe(&stats(1_000), start + ONE_SEC);
+        assert_eq!(budget.consumed(), Duration::ZERO);
+    }
+}
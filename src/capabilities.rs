@@ -0,0 +1,235 @@
+use std::{fmt::Display, fs, io, path::Path, str::FromStr};
+
+use crate::{
+    Cgroup,
+    cgroup::{self, MOUNTINFO},
+};
+
+/// PSI support of the running system, as reported by [`capabilities`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Capabilities {
+    /// Version of the running kernel.
+    pub kernel: KernelVersion,
+    /// Layout of the mounted cgroup hierarchies.
+    pub hierarchy: CgroupHierarchy,
+    /// Whether PSI is enabled, i.e. the kernel was built with
+    /// `CONFIG_PSI` and not booted with `psi=0`.
+    pub psi: bool,
+    /// Whether `/proc/pressure/irq` exists, which needs linux 6.1+ built
+    /// with `CONFIG_IRQ_TIME_ACCOUNTING`.
+    pub irq: bool,
+    /// Whether the cgroup of the current process has pressure files
+    /// that triggers can be registered on.
+    pub cgroup_triggers: bool,
+    /// Whether unprivileged processes may register triggers, which
+    /// linux allows since 6.5 for windows that are a multiple of
+    /// [`TriggerSpec::UNPRIVILEGED_WINDOW_STEP`](crate::TriggerSpec::UNPRIVILEGED_WINDOW_STEP).
+    pub unprivileged_triggers: bool,
+}
+
+/// Probes which PSI features the running system supports.
+///
+/// ```no_run
+/// # use presutaoru::*;
+/// let caps = capabilities().unwrap();
+/// if !caps.psi {
+///     eprintln!("psi is disabled, boot with psi=1");
+/// }
+/// ```
+pub fn capabilities() -> io::Result<Capabilities> {
+    const UNPRIVILEGED_TRIGGERS: KernelVersion = KernelVersion::new(6, 5, 0);
+
+    let kernel = KernelVersion::current()?;
+    let hierarchy = CgroupHierarchy::detect()?;
+    // /proc/pressure is only created when psi is enabled at boot
+    let psi = Path::new("/proc/pressure/cpu").is_file();
+    let irq = psi && Path::new("/proc/pressure/irq").is_file();
+    let cgroup_triggers =
+        psi && Cgroup::current().is_ok_and(|cgroup| cgroup.path().join("cpu.pressure").is_file());
+
+    Ok(Capabilities {
+        kernel,
+        hierarchy,
+        psi,
+        irq,
+        cgroup_triggers,
+        unprivileged_triggers: psi && kernel >= UNPRIVILEGED_TRIGGERS,
+    })
+}
+
+/// Layout of the mounted cgroup hierarchies.
+///
+/// Pressure files only exist on cgroup v2, so cgroup triggers are
+/// only available with [`CgroupHierarchy::Unified`] or, below the
+/// cgroup2 mount, with [`CgroupHierarchy::Hybrid`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CgroupHierarchy {
+    /// Only cgroup v2 is mounted.
+    Unified,
+    /// Both cgroup v1 controllers and a cgroup v2 hierarchy are mounted,
+    /// as in systemd's hybrid mode.
+    Hybrid,
+    /// Only cgroup v1 is mounted.
+    Legacy,
+    /// No cgroup filesystem is mounted.
+    None,
+}
+
+impl CgroupHierarchy {
+    /// Detects the hierarchy from `/proc/self/mountinfo`.
+    pub fn detect() -> io::Result<Self> {
+        Ok(Self::from_mountinfo(&fs::read_to_string(MOUNTINFO)?))
+    }
+
+    pub(crate) fn from_mountinfo(mountinfo: &str) -> Self {
+        let (mut v1, mut v2) = (false, false);
+        for mount in cgroup::mounts(mountinfo) {
+            match mount.fs_type {
+                "cgroup" => v1 = true,
+                "cgroup2" => v2 = true,
+                _ => {}
+            }
+        }
+        match (v1, v2) {
+            (false, true) => Self::Unified,
+            (true, true) => Self::Hybrid,
+            (true, false) => Self::Legacy,
+            (false, false) => Self::None,
+        }
+    }
+}
+
+/// Version of the running kernel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KernelVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl KernelVersion {
+    const OSRELEASE: &'static str = "/proc/sys/kernel/osrelease";
+
+    /// Creates a new [`KernelVersion`].
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Reads the version of the running kernel.
+    pub fn current() -> io::Result<Self> {
+        fs::read_to_string(Self::OSRELEASE)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Error returned when parsing a [`KernelVersion`] fails
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid kernel version {0:?}")]
+pub struct KernelVersionParseError(String);
+
+impl FromStr for KernelVersion {
+    type Err = KernelVersionParseError;
+
+    /// Parses a release string such as `6.1.0-13-amd64`.
+    ///
+    /// Anything after the numeric `major.minor[.patch]` prefix is ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || KernelVersionParseError(s.trim().into());
+
+        let numeric = s
+            .trim()
+            .split(|c: char| !c.is_ascii_digit() && c != '.')
+            .next()
+            .unwrap_or_default();
+        let mut parts = numeric.split('.').map(str::parse::<u32>);
+
+        let major = parts.next().and_then(Result::ok).ok_or_else(err)?;
+        let minor = parts.next().and_then(Result::ok).ok_or_else(err)?;
+        let patch = match parts.next() {
+            Some(patch) => patch.map_err(|_| err())?,
+            None => 0,
+        };
+
+        Ok(Self::new(major, minor, patch))
+    }
+}
+
+impl Display for KernelVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_kernel_version() {
+        let cases = [
+            ("6.1.0-13-amd64\n", KernelVersion::new(6, 1, 0)),
+            (
+                "5.15.153.1-microsoft-standard-WSL2",
+                KernelVersion::new(5, 15, 153),
+            ),
+            ("6.9", KernelVersion::new(6, 9, 0)),
+            ("6.10.0+", KernelVersion::new(6, 10, 0)),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(input.parse(), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn parse_rejects_invalid_version() {
+        for input in ["", "linux", "6", "6.x"] {
+            assert!(input.parse::<KernelVersion>().is_err());
+        }
+    }
+
+    #[test]
+    fn kernel_version_ordering() {
+        assert!(KernelVersion::new(6, 1, 0) > KernelVersion::new(5, 19, 17));
+        assert!(KernelVersion::new(6, 10, 0) > KernelVersion::new(6, 9, 12));
+    }
+
+    #[test]
+    fn detect_cgroup_hierarchy() {
+        let v1 = "33 24 0:29 / /sys/fs/cgroup/cpu rw,relatime - cgroup cgroup rw,cpu\n";
+        let v2 = "42 24 0:38 / /sys/fs/cgroup/unified rw,relatime - cgroup2 cgroup2 rw\n";
+        let other = "24 1 0:22 / /sys rw,nosuid - sysfs sysfs rw\n";
+
+        let cases = [
+            (format!("{other}{v2}"), CgroupHierarchy::Unified),
+            (format!("{other}{v1}{v2}"), CgroupHierarchy::Hybrid),
+            (format!("{other}{v1}"), CgroupHierarchy::Legacy),
+            (other.to_owned(), CgroupHierarchy::None),
+        ];
+
+        for (mountinfo, expected) in cases {
+            assert_eq!(CgroupHierarchy::from_mountinfo(&mountinfo), expected);
+        }
+    }
+
+    #[test]
+    fn probe_capabilities() {
+        let caps = capabilities().unwrap();
+
+        assert_eq!(caps.kernel, KernelVersion::current().unwrap());
+        assert!(caps.psi || !(caps.irq || caps.cgroup_triggers || caps.unprivileged_triggers));
+        if caps.cgroup_triggers {
+            assert_ne!(caps.hierarchy, CgroupHierarchy::Legacy);
+        }
+    }
+
+    #[test]
+    fn kernel_version_display() {
+        assert_eq!(KernelVersion::new(6, 1, 0).to_string(), "6.1.0");
+    }
+}
@@ -0,0 +1,339 @@
+use std::{
+    fs, io,
+    os::unix::fs::MetadataExt as _,
+    path::{Path, PathBuf},
+};
+
+use crate::{CgroupEntryType, CgroupHierarchy, PsiEntry, PsiFd, PsiFdBuilderError, TriggerSpec};
+
+/// A cgroup v2 directory.
+///
+/// [`PsiEntry::Cgroup`] borrows its path, so a resolved cgroup is kept
+/// here and entries are created from it with [`Cgroup::entry`].
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use presutaoru::*;
+/// # let pid = 1;
+/// let cgroup = Cgroup::of_pid(pid).unwrap();
+///
+/// let psi_fd = PsiFd::builder()
+///     .entry(cgroup.entry(CgroupEntryType::Memory))
+///     .stall_type(StallType::Full)
+///     .stall_amount(Duration::from_millis(100))
+///     .time_window(Duration::from_secs(1))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cgroup {
+    path: PathBuf,
+}
+
+/// Errors that can occur when resolving a [`Cgroup`]
+#[derive(thiserror::Error, Debug)]
+pub enum CgroupError {
+    #[error("cgroup2 filesystem is not mounted")]
+    NotMounted,
+    #[error("only cgroup v1 is mounted, pressure files require cgroup v2")]
+    Legacy,
+    #[error("process is not a member of a cgroup v2 hierarchy")]
+    NoMembership,
+    #[error("{0} is not delegated to the current user")]
+    NotDelegated(PathBuf),
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl Cgroup {
+    /// Wraps a cgroup v2 directory.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Resolves the cgroup of the process with the given pid
+    /// from `/proc/<pid>/cgroup`.
+    pub fn of_pid(pid: u32) -> Result<Self, CgroupError> {
+        Self::from_proc_cgroup(Path::new(&format!("/proc/{pid}/cgroup")))
+    }
+
+    /// Resolves the cgroup of the calling process from `/proc/self/cgroup`.
+    ///
+    /// Unlike looking up [`std::process::id`] with [`Cgroup::of_pid`],
+    /// this also works when `/proc` was mounted from another pid
+    /// namespace.
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use presutaoru::*;
+    /// let spec: TriggerSpec = "some 150000 1000000".parse().unwrap();
+    /// let psi_fd = Cgroup::current()
+    ///     .unwrap()
+    ///     .trigger(CgroupEntryType::Memory, spec)
+    ///     .unwrap();
+    /// ```
+    pub fn current() -> Result<Self, CgroupError> {
+        Self::from_proc_cgroup(Path::new("/proc/self/cgroup"))
+    }
+
+    fn from_proc_cgroup(proc_cgroup: &Path) -> Result<Self, CgroupError> {
+        let membership = fs::read_to_string(proc_cgroup)?;
+        let mountinfo = fs::read_to_string(MOUNTINFO)?;
+        let legacy = || CgroupHierarchy::from_mountinfo(&mountinfo) == CgroupHierarchy::Legacy;
+
+        let Some(relative) = unified_membership(&membership) else {
+            return Err(if legacy() {
+                CgroupError::Legacy
+            } else {
+                CgroupError::NoMembership
+            });
+        };
+        let Some(Mount {
+            root, mount_point, ..
+        }) = mounts(&mountinfo).find(|mount| mount.fs_type == "cgroup2")
+        else {
+            return Err(if legacy() {
+                CgroupError::Legacy
+            } else {
+                CgroupError::NotMounted
+            });
+        };
+
+        // Memberships are reported relative to the reader's cgroup namespace,
+        // while a bind-mounted hierarchy may start below that.
+        let relative = Path::new(relative);
+        let relative = relative.strip_prefix(&root).unwrap_or(relative);
+        let relative = relative.strip_prefix("/").unwrap_or(relative);
+
+        if relative.as_os_str().is_empty() {
+            return Ok(Self::new(mount_point));
+        }
+        Ok(Self::new(mount_point.join(relative)))
+    }
+
+    /// Locates the subtree systemd delegates to the current user's
+    /// service manager, `user@<uid>.service`, and checks that the
+    /// user owns it.
+    ///
+    /// Unprivileged processes can only register triggers on cgroups they
+    /// own, which in practice means cgroups below this one: the cgroup of
+    /// a process started by the user's service manager, or a child cgroup
+    /// created inside the subtree.
+    pub fn delegated() -> Result<Self, CgroupError> {
+        let uid = effective_uid()?;
+        let service = format!("user@{uid}.service");
+
+        let current = Self::current()?;
+        let path = match delegated_ancestor(&current.path, &service) {
+            Some(path) => path.to_path_buf(),
+            None => {
+                let mountinfo = fs::read_to_string(MOUNTINFO)?;
+                let mount = mounts(&mountinfo)
+                    .find(|mount| mount.fs_type == "cgroup2")
+                    .ok_or(CgroupError::NotMounted)?;
+                mount
+                    .mount_point
+                    .join("user.slice")
+                    .join(format!("user-{uid}.slice"))
+                    .join(service)
+            }
+        };
+
+        match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_dir() && metadata.uid() == uid => Ok(Self::new(path)),
+            Ok(_) => Err(CgroupError::NotDelegated(path)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Err(CgroupError::NotDelegated(path)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the cgroup directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the [`PsiEntry`] for the given type in this cgroup.
+    pub fn entry(&self, entry_type: CgroupEntryType) -> PsiEntry<'_> {
+        PsiEntry::Cgroup(entry_type, &self.path)
+    }
+
+    /// Registers a trigger on the pressure file of the given type in
+    /// this cgroup.
+    pub fn trigger(
+        &self,
+        entry_type: CgroupEntryType,
+        spec: TriggerSpec,
+    ) -> Result<PsiFd, PsiFdBuilderError> {
+        PsiFd::builder()
+            .entry(self.entry(entry_type))
+            .stall_type(spec.stall_type())
+            .stall_amount(spec.stall_amount())
+            .time_window(spec.time_window())
+            .build()
+    }
+}
+
+fn delegated_ancestor<'a>(path: &'a Path, service: &str) -> Option<&'a Path> {
+    path.ancestors()
+        .find(|ancestor| ancestor.file_name().is_some_and(|name| name == service))
+}
+
+/// Returns the effective uid from `/proc/self/status`.
+fn effective_uid() -> io::Result<u32> {
+    let status = fs::read_to_string("/proc/self/status")?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|uids| uids.split_ascii_whitespace().nth(1))
+        .and_then(|euid| euid.parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no uid in /proc/self/status"))
+}
+
+/// Returns the path of the `0::<path>` line in `/proc/<pid>/cgroup`.
+fn unified_membership(contents: &str) -> Option<&str> {
+    contents.lines().find_map(|line| {
+        let path = line.strip_prefix("0::")?;
+        Some(path.strip_suffix(" (deleted)").unwrap_or(path))
+    })
+}
+
+pub(crate) const MOUNTINFO: &str = "/proc/self/mountinfo";
+
+/// A line of `/proc/self/mountinfo`.
+///
+/// ```console
+/// 42 32 0:38 / /sys/fs/cgroup rw,relatime shared:9 - cgroup2 cgroup2 rw
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Mount<'a> {
+    pub(crate) root: PathBuf,
+    pub(crate) mount_point: PathBuf,
+    pub(crate) fs_type: &'a str,
+}
+
+pub(crate) fn mounts(mountinfo: &str) -> impl Iterator<Item = Mount<'_>> {
+    mountinfo.lines().filter_map(|line| {
+        let (mount, fs) = line.split_once(" - ")?;
+        let mut fields = mount.split(' ').skip(3);
+        Some(Mount {
+            root: unescape(fields.next()?),
+            mount_point: unescape(fields.next()?),
+            fs_type: fs.split(' ').next()?,
+        })
+    })
+}
+
+/// Returns the filesystem type of the mount `path` lies on.
+///
+/// The last mount covering `path` wins, as later mounts shadow earlier ones.
+pub(crate) fn fs_type_of(path: &Path) -> io::Result<Option<String>> {
+    let mountinfo = fs::read_to_string(MOUNTINFO)?;
+    Ok(mounts(&mountinfo)
+        .filter(|mount| path.starts_with(&mount.mount_point))
+        .max_by_key(|mount| mount.mount_point.components().count())
+        .map(|mount| mount.fs_type.to_owned()))
+}
+
+/// Decodes the octal escapes (`\040` for space etc.) used in mountinfo.
+fn unescape(field: &str) -> PathBuf {
+    let mut out = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(pos) = rest.find('\\') {
+        out.push_str(&rest[..pos]);
+        let escaped = rest.get(pos + 1..pos + 4);
+        match escaped.and_then(|oct| u8::from_str_radix(oct, 8).ok()) {
+            Some(byte) => {
+                out.push(byte as char);
+                rest = &rest[pos + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[pos + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    PathBuf::from(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_unified_membership() {
+        let cases = [
+            (
+                "0::/system.slice/foo.service\n",
+                Some("/system.slice/foo.service"),
+            ),
+            ("4:memory:/user\n0::/user.slice\n", Some("/user.slice")),
+            ("0::/gone (deleted)\n", Some("/gone")),
+            ("4:memory:/user\n1:name=systemd:/\n", None),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(unified_membership(input), expected);
+        }
+    }
+
+    #[test]
+    fn parse_mounts() {
+        let mountinfo = "\
+            24 1 0:22 / /sys rw,nosuid - sysfs sysfs rw\n\
+            33 24 0:29 / /sys/fs/cgroup/cpu rw,relatime - cgroup cgroup rw,cpu\n\
+            42 24 0:38 /ns /sys/fs/cgroup/my\\040tree rw,relatime shared:9 - cgroup2 cgroup2 rw\n";
+
+        let mounts = mounts(mountinfo).collect::<Vec<_>>();
+
+        assert_eq!(mounts.len(), 3);
+        assert_eq!(mounts[1].fs_type, "cgroup");
+        assert_eq!(
+            mounts[2],
+            Mount {
+                root: PathBuf::from("/ns"),
+                mount_point: PathBuf::from("/sys/fs/cgroup/my tree"),
+                fs_type: "cgroup2",
+            }
+        );
+    }
+
+    #[test]
+    fn find_delegated_ancestor() {
+        let path = Path::new(
+            "/sys/fs/cgroup/user.slice/user-1000.slice/user@1000.service/app.slice/foo.service",
+        );
+
+        assert_eq!(
+            delegated_ancestor(path, "user@1000.service"),
+            Some(Path::new(
+                "/sys/fs/cgroup/user.slice/user-1000.slice/user@1000.service"
+            ))
+        );
+        assert_eq!(delegated_ancestor(path, "user@1001.service"), None);
+    }
+
+    #[test]
+    fn entry_uses_cgroup_path() {
+        let cgroup = Cgroup::new("/sys/fs/cgroup/test");
+
+        assert_eq!(
+            cgroup.entry(CgroupEntryType::Io).path(),
+            Path::new("/sys/fs/cgroup/test/io.pressure")
+        );
+    }
+
+    #[test]
+    fn of_current_pid() {
+        let cgroup = Cgroup::of_pid(std::process::id()).unwrap();
+
+        assert!(cgroup.path().is_dir());
+    }
+
+    #[test]
+    fn current_matches_own_pid() {
+        let cgroup = Cgroup::current().unwrap();
+
+        assert_eq!(cgroup, Cgroup::of_pid(std::process::id()).unwrap());
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+/// Coalesces trigger firings so that at most one per key is delivered
+/// every interval.
+///
+/// Under sustained pressure a trigger fires once per time window, which
+/// may be more often than the consumer wants to react. Keys identify
+/// the triggers, e.g. an index or the watched cgroup.
+///
+/// ```
+/// # use std::time::{Duration, Instant};
+/// # use presutaoru::*;
+/// let mut debouncer = Debouncer::new(Duration::from_secs(10));
+/// let start = Instant::now();
+///
+/// assert_eq!(debouncer.accept("memory", start), Some(0));
+/// assert_eq!(debouncer.accept("memory", start + Duration::from_secs(2)), None);
+/// assert_eq!(debouncer.accept("memory", start + Duration::from_secs(10)), Some(1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Debouncer<K> {
+    min_interval: Duration,
+    keys: HashMap<K, (Instant, u32)>,
+}
+
+impl<K: Eq + Hash> Debouncer<K> {
+    /// Creates a debouncer delivering at most one firing per key every
+    /// `min_interval`.
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Records a firing of `key` at `now` and returns whether to deliver
+    /// it.
+    ///
+    /// Returns the number of firings coalesced since the last delivered
+    /// one if this one should be delivered, `None` if it is coalesced.
+    pub fn accept(&mut self, key: K, now: Instant) -> Option<u32> {
+        match self.keys.get_mut(&key) {
+            Some((last, coalesced)) => {
+                if now.saturating_duration_since(*last) < self.min_interval {
+                    *coalesced = coalesced.saturating_add(1);
+                    return None;
+                }
+                *last = now;
+                Some(std::mem::take(coalesced))
+            }
+            None => {
+                self.keys.insert(key, (now, 0));
+                Some(0)
+            }
+        }
+    }
+
+    /// Forgets a key, e.g. after its trigger was dropped, so that its
+    /// next firing is delivered immediately.
+    pub fn remove(&mut self, key: &K) {
+        self.keys.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE_SEC: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn coalesces_bursts_per_key() {
+        let mut debouncer = Debouncer::new(ONE_SEC * 10);
+        let start = Instant::now();
+
+        assert_eq!(debouncer.accept(1, start), Some(0));
+        assert_eq!(debouncer.accept(2, start + ONE_SEC), Some(0));
+        for n in 2..=9 {
+            assert_eq!(debouncer.accept(1, start + ONE_SEC * n), None);
+        }
+
+        assert_eq!(debouncer.accept(1, start + ONE_SEC * 10), Some(8));
+        assert_eq!(debouncer.accept(2, start + ONE_SEC * 10), None);
+        assert_eq!(debouncer.accept(1, start + ONE_SEC * 25), Some(0));
+    }
+
+    #[test]
+    fn removed_key_is_delivered_immediately() {
+        let mut debouncer = Debouncer::new(ONE_SEC * 10);
+        let start = Instant::now();
+
+        debouncer.accept("cpu", start);
+        debouncer.remove(&"cpu");
+
+        assert_eq!(debouncer.accept("cpu", start + ONE_SEC), Some(0));
+    }
+}
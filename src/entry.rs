@@ -1,6 +1,15 @@
-use std::{borrow::Cow, fmt::Display, path::Path};
+use std::{borrow::Cow, fmt::Display, fs::File, io, path::Path};
+
+use crate::{PsiStats, stats};
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+#[non_exhaustive]
 pub enum GlobalEntryType {
     Cpu,
     Io,
@@ -9,6 +18,13 @@ pub enum GlobalEntryType {
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+#[non_exhaustive]
 pub enum CgroupEntryType {
     Cpu,
     Io,
@@ -16,12 +32,27 @@ pub enum CgroupEntryType {
 }
 
 /// PsiEntry types
+///
+/// New kinds of entries may be added in minor releases. Pressure files
+/// this crate does not know about yet can be used through
+/// [`PsiEntry::Custom`].
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+#[non_exhaustive]
 pub enum PsiEntry<'a> {
     /// path to `/proc/pressure/[type]`
     Global(GlobalEntryType),
     /// use the given cgroup directory, path to `[dir]/[type]`
-    Cgroup(CgroupEntryType, &'a Path),
+    Cgroup(
+        CgroupEntryType,
+        #[cfg_attr(feature = "serde", serde(borrow))] &'a Path,
+    ),
+    /// use the given pressure file as is
+    Custom(#[cfg_attr(feature = "serde", serde(borrow))] &'a Path),
 }
 
 impl<'a> PsiEntry<'a> {
@@ -55,8 +86,29 @@ impl<'a> PsiEntry<'a> {
                 };
                 Cow::Owned(base.join(file))
             }
+
+            Self::Custom(path) => Cow::Borrowed(path),
         }
     }
+
+    /// Reads the current contents of the PSI file.
+    ///
+    /// Unlike [`PsiFd::read_stats`](crate::PsiFd::read_stats), this needs
+    /// no trigger and thus no write access to the file.
+    pub fn read(&self) -> io::Result<PsiStats> {
+        stats::read_from(&File::open(self.path())?)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for PsiEntry<'a> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(match u.int_in_range(0..=2u8)? {
+            0 => Self::Global(u.arbitrary()?),
+            1 => Self::Cgroup(u.arbitrary()?, Path::new(u.arbitrary::<&'a str>()?)),
+            _ => Self::Custom(Path::new(u.arbitrary::<&'a str>()?)),
+        })
+    }
 }
 
 impl<'a> Display for PsiEntry<'a> {
@@ -98,6 +150,61 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_entry() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let data = [0u8, 2, 1, b'/', b'a', b'b'];
+        let mut u = Unstructured::new(&data);
+
+        for _ in 0..4 {
+            let entry = PsiEntry::arbitrary(&mut u).unwrap();
+            let path = entry.path();
+            if let PsiEntry::Custom(custom) = entry {
+                assert_eq!(path, custom);
+                continue;
+            }
+            assert!(
+                path.starts_with("/proc/pressure")
+                    || path.extension().is_some_and(|ext| ext == "pressure")
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_entry() {
+        let json = r#"[{"global":"irq"},{"cgroup":["memory","/sys/fs/cgroup/a"]},{"custom":"/x.pressure"}]"#;
+
+        let entries: Vec<PsiEntry<'_>> = serde_json::from_str(json).unwrap();
+        let paths: Vec<_> = entries.iter().map(PsiEntry::path).collect();
+        assert_eq!(
+            paths,
+            [
+                Path::new("/proc/pressure/irq"),
+                Path::new("/sys/fs/cgroup/a/memory.pressure"),
+                Path::new("/x.pressure"),
+            ]
+        );
+        assert_eq!(serde_json::to_string(&entries).unwrap(), json);
+    }
+
+    #[test]
+    fn custom_path() {
+        let path = Path::new("/sys/fs/cgroup/test/foo.pressure");
+
+        assert_eq!(PsiEntry::Custom(path).path(), path);
+    }
+
+    #[test]
+    fn read_global_entry() {
+        let stats = PsiEntry::Global(GlobalEntryType::Memory).read().unwrap();
+
+        assert!(stats.some.is_some());
+        assert!(stats.full.is_some());
+    }
+
     #[test]
     fn display_matches_path() {
         let cgroup_path = Path::new("/tmp");
@@ -110,6 +217,7 @@ mod tests {
             PsiEntry::Cgroup(CgroupEntryType::Cpu, cgroup_path),
             PsiEntry::Cgroup(CgroupEntryType::Io, cgroup_path),
             PsiEntry::Cgroup(CgroupEntryType::Memory, cgroup_path),
+            PsiEntry::Custom(Path::new("/tmp/foo.pressure")),
         ];
 
         for entry in entries {
@@ -0,0 +1,197 @@
+use std::{collections::VecDeque, time::Duration};
+
+/// Shape of a pressure episode, as labelled by [`EpisodeClassifier`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EpisodeKind {
+    /// A short, isolated burst such as a compile job.
+    Spike,
+    /// Pressure that outlasted the spike duration, such as a leak.
+    Sustained,
+    /// An episode recurring at a regular interval, such as a cron job.
+    Periodic,
+}
+
+/// Labels pressure episodes by their timing, so that a brief burst can
+/// be handled differently from persistent pressure.
+///
+/// An episode is the time from a trigger first firing until it stays
+/// quiet again, as tracked by the caller. Episodes at most the spike
+/// duration long are [`EpisodeKind::Spike`], longer ones
+/// [`EpisodeKind::Sustained`]. Once enough episodes started at a regular
+/// interval, each further one on that rhythm is
+/// [`EpisodeKind::Periodic`], regardless of its length.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use presutaoru::*;
+/// let mut classifier = EpisodeClassifier::new(Duration::from_secs(5));
+///
+/// let kind = classifier.classify(Duration::from_secs(0), Duration::from_secs(2));
+/// assert_eq!(kind, EpisodeKind::Spike);
+///
+/// let kind = classifier.classify(Duration::from_secs(60), Duration::from_secs(90));
+/// assert_eq!(kind, EpisodeKind::Sustained);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpisodeClassifier {
+    spike: Duration,
+    periodic_after: usize,
+    tolerance: f32,
+    starts: VecDeque<Duration>,
+}
+
+impl EpisodeClassifier {
+    /// Creates a classifier treating episodes up to `spike` long as
+    /// spikes.
+    ///
+    /// By default, four episodes whose intervals deviate by at most 20%
+    /// from their mean are considered periodic.
+    pub fn new(spike: Duration) -> Self {
+        Self {
+            spike,
+            periodic_after: 4,
+            tolerance: 0.2,
+            starts: VecDeque::new(),
+        }
+    }
+
+    /// Sets how many consecutive episodes, including the current one,
+    /// must start at a regular interval to be considered periodic.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `episodes` is less than 3, as a single interval has no
+    /// rhythm to compare against.
+    pub fn periodic_after(mut self, episodes: usize) -> Self {
+        assert!(episodes >= 3, "at least 3 episodes are needed for a period");
+        self.periodic_after = episodes;
+        self
+    }
+
+    /// Sets how far each interval may deviate from their mean, relative
+    /// to the mean, for episodes to be considered periodic.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tolerance` is negative.
+    pub fn tolerance(mut self, tolerance: f32) -> Self {
+        assert!(tolerance >= 0.0, "tolerance must not be negative");
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Labels an episode that started at `start`, measured from any fixed
+    /// point such as the start of monitoring, and lasted `duration`.
+    ///
+    /// Episodes must be reported in the order they started. One starting
+    /// before the previous episode resets the rhythm.
+    pub fn classify(&mut self, start: Duration, duration: Duration) -> EpisodeKind {
+        if self.starts.back().is_some_and(|&last| start < last) {
+            self.starts.clear();
+        }
+        if self.starts.len() == self.periodic_after {
+            self.starts.pop_front();
+        }
+        self.starts.push_back(start);
+
+        if self.is_periodic() {
+            EpisodeKind::Periodic
+        } else if duration <= self.spike {
+            EpisodeKind::Spike
+        } else {
+            EpisodeKind::Sustained
+        }
+    }
+
+    /// Returns the interval between recent episodes if they are periodic.
+    pub fn period(&self) -> Option<Duration> {
+        if !self.is_periodic() {
+            return None;
+        }
+        let first = self.starts.front()?;
+        let last = self.starts.back()?;
+        Some((*last - *first) / (self.starts.len() - 1) as u32)
+    }
+
+    /// Forgets the recorded episodes.
+    pub fn reset(&mut self) {
+        self.starts.clear();
+    }
+
+    fn is_periodic(&self) -> bool {
+        if self.starts.len() < self.periodic_after {
+            return false;
+        }
+
+        let intervals = || {
+            self.starts
+                .iter()
+                .zip(self.starts.iter().skip(1))
+                .map(|(prev, next)| (*next - *prev).as_secs_f32())
+        };
+        let mean = intervals().sum::<f32>() / (self.starts.len() - 1) as f32;
+
+        mean > 0.0 && intervals().all(|interval| (interval - mean).abs() <= self.tolerance * mean)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE_SEC: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn spike_and_sustained() {
+        let mut classifier = EpisodeClassifier::new(ONE_SEC * 5);
+
+        assert_eq!(
+            classifier.classify(Duration::ZERO, ONE_SEC * 5),
+            EpisodeKind::Spike
+        );
+        assert_eq!(
+            classifier.classify(ONE_SEC * 10, ONE_SEC * 6),
+            EpisodeKind::Sustained
+        );
+        assert_eq!(classifier.period(), None);
+    }
+
+    #[test]
+    fn regular_episodes_are_periodic() {
+        let mut classifier = EpisodeClassifier::new(ONE_SEC * 5);
+
+        for start in [0, 60, 121] {
+            assert_eq!(
+                classifier.classify(ONE_SEC * start, ONE_SEC),
+                EpisodeKind::Spike
+            );
+        }
+        assert_eq!(
+            classifier.classify(ONE_SEC * 179, ONE_SEC * 30),
+            EpisodeKind::Periodic
+        );
+        assert_eq!(classifier.period(), Some(ONE_SEC * 179 / 3));
+
+        // Breaking the rhythm falls back to the episode's length
+        assert_eq!(
+            classifier.classify(ONE_SEC * 200, ONE_SEC * 30),
+            EpisodeKind::Sustained
+        );
+    }
+
+    #[test]
+    fn out_of_order_resets_rhythm() {
+        let mut classifier = EpisodeClassifier::new(ONE_SEC * 5).periodic_after(3);
+
+        for start in [100, 110, 120] {
+            classifier.classify(ONE_SEC * start, ONE_SEC);
+        }
+        assert!(classifier.period().is_some());
+
+        assert_eq!(
+            classifier.classify(ONE_SEC * 50, ONE_SEC),
+            EpisodeKind::Spike
+        );
+        assert_eq!(classifier.period(), None);
+    }
+}
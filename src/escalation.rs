@@ -0,0 +1,173 @@
+/// Level of an [`Escalation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EscalationLevel {
+    Normal,
+    Warning,
+    Critical,
+}
+
+/// Escalates a condition that holds over consecutive windows.
+///
+/// The condition is evaluated once per window by the caller, e.g. whether
+/// a trigger fired during the window. It escalates to
+/// [`EscalationLevel::Warning`] after holding for `warning_after` windows
+/// and to [`EscalationLevel::Critical`] after `critical_after`. It is
+/// resolved back to [`EscalationLevel::Normal`] once it has not held for
+/// `resolve_after` windows in a row.
+///
+/// ```
+/// # use presutaoru::*;
+/// let mut escalation = Escalation::new(2, 4, 3);
+///
+/// let levels = [true, true, true, true, false, false, false]
+///     .into_iter()
+///     .map(|holds| escalation.update(holds))
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(
+///     levels,
+///     [
+///         None,
+///         Some(EscalationLevel::Warning),
+///         None,
+///         Some(EscalationLevel::Critical),
+///         None,
+///         None,
+///         Some(EscalationLevel::Normal),
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Escalation {
+    warning_after: u32,
+    critical_after: u32,
+    resolve_after: u32,
+    held: u32,
+    quiet: u32,
+    level: EscalationLevel,
+}
+
+impl Escalation {
+    /// Creates a new [`Escalation`] at [`EscalationLevel::Normal`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `warning_after` or `resolve_after` is zero, or if
+    /// `critical_after` is less than `warning_after`.
+    pub fn new(warning_after: u32, critical_after: u32, resolve_after: u32) -> Self {
+        assert!(warning_after > 0, "warning_after must not be zero");
+        assert!(
+            critical_after >= warning_after,
+            "critical_after must not be less than warning_after"
+        );
+        assert!(resolve_after > 0, "resolve_after must not be zero");
+
+        Self {
+            warning_after,
+            critical_after,
+            resolve_after,
+            held: 0,
+            quiet: 0,
+            level: EscalationLevel::Normal,
+        }
+    }
+
+    /// Records whether the condition held during the last window.
+    ///
+    /// Returns the new level if it changed.
+    /// A change to [`EscalationLevel::Normal`] means the condition was resolved.
+    pub fn update(&mut self, holds: bool) -> Option<EscalationLevel> {
+        let level = if holds {
+            self.quiet = 0;
+            self.held = self.held.saturating_add(1);
+            if self.held >= self.critical_after {
+                EscalationLevel::Critical
+            } else if self.held >= self.warning_after {
+                EscalationLevel::Warning
+            } else {
+                self.level
+            }
+        } else if self.level == EscalationLevel::Normal {
+            self.held = 0;
+            EscalationLevel::Normal
+        } else {
+            // Flapping conditions keep escalating until they are quiet
+            // for long enough to be resolved.
+            self.quiet += 1;
+            if self.quiet >= self.resolve_after {
+                self.held = 0;
+                self.quiet = 0;
+                EscalationLevel::Normal
+            } else {
+                self.level
+            }
+        };
+
+        (level != self.level).then(|| {
+            self.level = level;
+            level
+        })
+    }
+
+    /// Returns the current level.
+    pub fn level(&self) -> EscalationLevel {
+        self.level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(escalation: &mut Escalation, windows: &[bool]) -> Vec<Option<EscalationLevel>> {
+        windows
+            .iter()
+            .map(|&holds| escalation.update(holds))
+            .collect()
+    }
+
+    #[test]
+    fn short_conditions_do_not_escalate() {
+        let mut escalation = Escalation::new(3, 5, 2);
+
+        let changes = run(&mut escalation, &[true, true, false, true, true, false]);
+
+        assert!(changes.iter().all(Option::is_none));
+        assert_eq!(escalation.level(), EscalationLevel::Normal);
+    }
+
+    #[test]
+    fn flapping_keeps_escalating() {
+        let mut escalation = Escalation::new(1, 3, 2);
+
+        let changes = run(&mut escalation, &[true, false, true, false, true]);
+
+        assert_eq!(
+            changes,
+            [
+                Some(EscalationLevel::Warning),
+                None,
+                None,
+                None,
+                Some(EscalationLevel::Critical),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_from_warning() {
+        let mut escalation = Escalation::new(1, 10, 1);
+
+        assert_eq!(escalation.update(true), Some(EscalationLevel::Warning));
+        assert_eq!(escalation.update(false), Some(EscalationLevel::Normal));
+        assert_eq!(escalation.update(true), Some(EscalationLevel::Warning));
+    }
+
+    #[test]
+    fn critical_may_skip_warning() {
+        let mut escalation = Escalation::new(2, 2, 1);
+
+        assert_eq!(escalation.update(true), None);
+        assert_eq!(escalation.update(true), Some(EscalationLevel::Critical));
+    }
+}
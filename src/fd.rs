@@ -1,21 +1,32 @@
 use std::{
     fmt::Display,
-    fs::OpenOptions,
-    io::{self, Cursor, Write},
+    fs::{self, File, OpenOptions},
+    io::{self, Cursor, Read, Write},
     os::{
-        fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd},
+        fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd},
         unix::fs::OpenOptionsExt as _,
     },
-    path::PathBuf,
+    path::{Path, PathBuf},
+    str::FromStr,
     time::Duration,
 };
 
-use crate::PsiEntry;
+use crate::{GlobalEntryType, KernelVersion, PsiEntry, PsiStats, cgroup, stats};
 
 // Linux UAPI: include/uapi/asm-generic/fcntl.h
 const O_NONBLOCK: i32 = 0o4000;
-
-#[derive(Debug, Clone, Copy)]
+// Linux UAPI: include/uapi/asm-generic/errno-base.h
+const ENFILE: i32 = 23;
+const EMFILE: i32 = 24;
+const EROFS: i32 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum StallType {
     Some,
     Full,
@@ -36,9 +47,9 @@ impl Display for StallType {
 ///
 /// <https://docs.kernel.org/accounting/psi.html>
 #[derive(Debug)]
-#[repr(transparent)]
 pub struct PsiFd {
-    fd: OwnedFd,
+    file: File,
+    trigger: Option<TriggerSpec>,
 }
 
 impl PsiFd {
@@ -51,35 +62,310 @@ impl PsiFd {
     /// The provided file descriptor must refer to a PSI
     /// file with a successfully registered trigger.
     pub unsafe fn new_unchecked(fd: OwnedFd) -> Self {
-        Self { fd }
+        Self {
+            file: File::from(fd),
+            trigger: None,
+        }
+    }
+
+    /// Returns the trigger registered on the fd.
+    ///
+    /// This is known for fds from [`PsiFdBuilder::build`], and for those
+    /// given one with [`PsiFd::with_trigger`]. It is `None` for fds
+    /// wrapped otherwise, as the kernel does not report it.
+    pub fn trigger(&self) -> Option<TriggerSpec> {
+        self.trigger
+    }
+
+    /// Records the trigger registered on the fd, for [`PsiFd::trigger`].
+    ///
+    /// This does not register anything with the kernel.
+    pub fn with_trigger(mut self, spec: TriggerSpec) -> Self {
+        self.trigger = Some(spec);
+        self
+    }
+
+    /// Returns the path of the PSI file the fd refers to.
+    pub fn path(&self) -> io::Result<PathBuf> {
+        fd_path(self.file.as_raw_fd())
+    }
+
+    /// Reads the current contents of the PSI file.
+    ///
+    /// The file is always read from the beginning, regardless of
+    /// the position left by previous [`Read`] calls.
+    pub fn read_stats(&self) -> io::Result<PsiStats> {
+        stats::read_from(&self.file)
+    }
+}
+
+impl Read for PsiFd {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
     }
 }
 
 impl AsRawFd for PsiFd {
     fn as_raw_fd(&self) -> RawFd {
-        self.fd.as_raw_fd()
+        self.file.as_raw_fd()
     }
 }
 
 impl AsFd for PsiFd {
     fn as_fd(&self) -> BorrowedFd<'_> {
-        self.fd.as_fd()
+        self.file.as_fd()
+    }
+}
+
+impl IntoRawFd for PsiFd {
+    fn into_raw_fd(self) -> RawFd {
+        self.file.into_raw_fd()
+    }
+}
+
+impl FromRawFd for PsiFd {
+    /// # Safety
+    /// The provided file descriptor must be open, owned by the caller,
+    /// and refer to a PSI file with a successfully registered trigger.
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        Self::new_unchecked(OwnedFd::from_raw_fd(fd))
     }
 }
 
 impl From<PsiFd> for OwnedFd {
     fn from(value: PsiFd) -> Self {
-        value.fd
+        value.file.into()
+    }
+}
+
+impl TryFrom<OwnedFd> for PsiFd {
+    type Error = io::Error;
+
+    /// Wraps an [`OwnedFd`] after checking that it refers to a PSI file.
+    ///
+    /// The file must be named like a PSI file and live on procfs, for
+    /// `/proc/pressure`, or on a cgroup v2 hierarchy. Whether a trigger
+    /// was registered on the file cannot be observed from userspace, so
+    /// it is not verified. The fd is closed if validation fails.
+    fn try_from(fd: OwnedFd) -> Result<Self, Self::Error> {
+        let path = fd_path(fd.as_raw_fd())?;
+        if !is_psi_path(&path) || !is_psi_fs(&path)? {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a psi file", path.display()),
+            ));
+        }
+
+        // SAFETY:
+        // The fd refers to a PSI file
+        Ok(unsafe { PsiFd::new_unchecked(fd) })
+    }
+}
+
+fn fd_path(fd: RawFd) -> io::Result<PathBuf> {
+    fs::read_link(format!("/proc/self/fd/{fd}"))
+}
+
+/// Returns whether `path` is named like a PSI file, without looking at
+/// its filesystem.
+pub(crate) fn is_psi_path(path: &Path) -> bool {
+    if path.starts_with("/proc/pressure") {
+        return true;
+    }
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(".pressure"))
+}
+
+/// Returns whether `path`, named like a PSI file, lies on the filesystem
+/// such a file belongs to.
+fn is_psi_fs(path: &Path) -> io::Result<bool> {
+    let expected = if path.starts_with("/proc/pressure") {
+        "proc"
+    } else {
+        "cgroup2"
+    };
+    Ok(cgroup::fs_type_of(path)?.as_deref() == Some(expected))
+}
+
+/// A validated PSI trigger.
+///
+/// ```console
+/// <some|full> <stall amount in us> <time window in us>
+/// ```
+///
+/// The [`Display`] implementation yields the trigger string
+/// without the trailing newline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TriggerSpec {
+    stall_type: StallType,
+    stall_amount: Duration,
+    time_window: Duration,
+}
+
+impl TriggerSpec {
+    /// Granularity of unprivileged trigger windows.
+    pub const UNPRIVILEGED_WINDOW_STEP: Duration = Duration::from_secs(2);
+
+    /// Creates a new [`TriggerSpec`], validating it against the kernel limits.
+    ///
+    /// See [`PsiFdBuilder::stall_amount`] and [`PsiFdBuilder::time_window`].
+    /// The kernel takes both durations in whole microseconds, so they are
+    /// truncated to microseconds after validation.
+    pub fn new(
+        stall_type: StallType,
+        stall_amount: Duration,
+        time_window: Duration,
+    ) -> Result<Self, PsiFdBuilderError> {
+        if time_window < Duration::from_millis(500) {
+            return Err(PsiFdBuilderError::TimeWindowTooSmall);
+        }
+        if time_window > Duration::from_secs(10) {
+            return Err(PsiFdBuilderError::TimeWindowTooLarge);
+        }
+        if stall_amount < Duration::from_micros(1) {
+            return Err(PsiFdBuilderError::StallAmountTooSmall);
+        }
+        if stall_amount > time_window {
+            return Err(PsiFdBuilderError::StallAmountExceedsTimeWindow);
+        }
+
+        Ok(Self {
+            stall_type,
+            stall_amount: truncate_to_micros(stall_amount),
+            time_window: truncate_to_micros(time_window),
+        })
+    }
+
+    /// Rounds the time window up to the granularity the kernel requires
+    /// of unprivileged triggers.
+    ///
+    /// Since linux 6.5, processes without `CAP_SYS_RESOURCE` can create
+    /// triggers, but only with a time window that is a multiple of
+    /// [`TriggerSpec::UNPRIVILEGED_WINDOW_STEP`]. The kernel rejects other
+    /// windows with `EINVAL`.
+    pub fn round_for_unprivileged(self) -> Self {
+        let step = Self::UNPRIVILEGED_WINDOW_STEP.as_micros();
+        let window = (self.time_window.as_micros() + step - 1) / step * step;
+
+        Self {
+            time_window: Duration::from_micros(window as u64),
+            ..self
+        }
+    }
+
+    /// Returns the [`StallType`].
+    pub fn stall_type(&self) -> StallType {
+        self.stall_type
+    }
+
+    /// Returns the accumulated stall duration threshold.
+    pub fn stall_amount(&self) -> Duration {
+        self.stall_amount
+    }
+
+    /// Returns the PSI observation window.
+    pub fn time_window(&self) -> Duration {
+        self.time_window
+    }
+}
+
+impl Display for TriggerSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.stall_type,
+            self.stall_amount.as_micros(),
+            self.time_window.as_micros(),
+        )
+    }
+}
+
+/// Errors that can occur when parsing a [`TriggerSpec`]
+#[derive(thiserror::Error, Debug)]
+pub enum TriggerSpecParseError {
+    #[error("malformed trigger {0:?}")]
+    Malformed(String),
+    #[error("invalid trigger: {0}")]
+    Invalid(#[from] PsiFdBuilderError),
+}
+
+impl FromStr for TriggerSpec {
+    type Err = TriggerSpecParseError;
+
+    /// Parses a trigger string such as `some 150000 1000000`.
+    ///
+    /// The parsed trigger is validated like [`TriggerSpec::new`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || TriggerSpecParseError::Malformed(s.into());
+
+        let mut fields = s.split_ascii_whitespace();
+        let stall_type = match fields.next() {
+            Some("some") => StallType::Some,
+            Some("full") => StallType::Full,
+            _ => return Err(malformed()),
+        };
+        let mut micros = || -> Result<Duration, TriggerSpecParseError> {
+            let us = fields.next().and_then(|f| f.parse().ok());
+            us.map(Duration::from_micros).ok_or_else(malformed)
+        };
+        let stall_amount = micros()?;
+        let time_window = micros()?;
+        if fields.next().is_some() {
+            return Err(malformed());
+        }
+
+        Ok(Self::new(stall_type, stall_amount, time_window)?)
+    }
+}
+
+fn truncate_to_micros(duration: Duration) -> Duration {
+    Duration::new(duration.as_secs(), duration.subsec_micros() * 1000)
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for TriggerSpec {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let time_window = u.int_in_range(500_000..=10_000_000)?;
+        let stall_amount = u.int_in_range(1..=time_window)?;
+
+        Ok(Self {
+            stall_type: u.arbitrary()?,
+            stall_amount: Duration::from_micros(stall_amount),
+            time_window: Duration::from_micros(time_window),
+        })
+    }
+}
+
+/// Serialized as the trigger string, e.g. `"some 150000 1000000"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for TriggerSpec {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserialized from the trigger string, validated like
+/// [`TriggerSpec::new`].
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TriggerSpec {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <String as serde::Deserialize>::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
     }
 }
 
 /// Builder for [`PsiFd`]
 #[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct PsiFdBuilder<'a> {
     entry: Option<PsiEntry<'a>>,
     stall_type: Option<StallType>,
     stall_amount: Option<Duration>,
     time_window: Option<Duration>,
+    strict: bool,
 }
 
 /// Errors that can occur when building a [`PsiFd`]
@@ -101,8 +387,24 @@ pub enum PsiFdBuilderError {
     StallAmountTooSmall,
     #[error("stall amount must not exceed the time window")]
     StallAmountExceedsTimeWindow,
+    #[error("stall amount and time window must be whole microseconds")]
+    SubMicrosecondPrecision,
     #[error("no psi entry found {0}")]
     NoPsiEntry(PathBuf),
+    #[error("{0} is on a cgroup v1 hierarchy, pressure files only exist on cgroup v2")]
+    CgroupV1(PathBuf),
+    #[error("irq pressure only tracks full stalls, some is not supported")]
+    IrqSomeUnsupported,
+    /// Full cpu stalls are undefined system-wide. Since linux 5.13 the
+    /// kernel accepts such a trigger, but it never fires.
+    #[error("full cpu stalls are undefined system-wide, the trigger would never fire")]
+    FullCpuUnsupported,
+    #[error("irq pressure requires linux >= 6.1 with CONFIG_IRQ_TIME_ACCOUNTING (running {0})")]
+    IrqUnsupported(KernelVersion),
+    #[error("trigger limit reached, no file descriptors left to open {0}")]
+    TriggerLimitReached(PathBuf),
+    #[error("{0} does not accept triggers: {1}")]
+    TriggerRejected(PathBuf, #[source] io::Error),
     #[error("io error: {0}")]
     Io(#[from] io::Error),
 }
@@ -143,27 +445,39 @@ impl<'a> PsiFdBuilder<'a> {
         self
     }
 
+    /// Rejects durations that are not whole microseconds with
+    /// [`PsiFdBuilderError::SubMicrosecondPrecision`].
+    ///
+    /// By default they are truncated to microseconds, see [`TriggerSpec::new`].
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
     /// Build the [`PsiFd`].
     ///
     /// This opens the underlying [`PsiEntry`] and registers
     /// the configured trigger with the kernel.
+    ///
+    /// Entries that are readable but refuse triggers, e.g. on kernels
+    /// with a read-only `/proc/pressure`, fail with
+    /// [`PsiFdBuilderError::TriggerRejected`]. Callers that want to run
+    /// uniformly across such hosts can skip the entry or fall back to
+    /// sampling it with [`PsiSampler::for_trigger`](crate::PsiSampler::for_trigger).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(entry = ?self.entry), err)
+    )]
     pub fn build(self) -> Result<PsiFd, PsiFdBuilderError> {
         let entry = self.entry.ok_or(PsiFdBuilderError::NoEntry)?;
         let stall_type = self.stall_type.ok_or(PsiFdBuilderError::NoStallType)?;
         let stall_amount = self.stall_amount.ok_or(PsiFdBuilderError::NoStallAmount)?;
         let time_window = self.time_window.ok_or(PsiFdBuilderError::NoTimeWindow)?;
-        if time_window < Duration::from_millis(500) {
-            return Err(PsiFdBuilderError::TimeWindowTooSmall);
-        }
-        if time_window > Duration::from_secs(10) {
-            return Err(PsiFdBuilderError::TimeWindowTooLarge);
-        }
-        if stall_amount < Duration::from_micros(1) {
-            return Err(PsiFdBuilderError::StallAmountTooSmall);
-        }
-        if stall_amount > time_window {
-            return Err(PsiFdBuilderError::StallAmountExceedsTimeWindow);
+        let spec = TriggerSpec::new(stall_type, stall_amount, time_window)?;
+        if self.strict && (spec.stall_amount, spec.time_window) != (stall_amount, time_window) {
+            return Err(PsiFdBuilderError::SubMicrosecondPrecision);
         }
+        check_stall_type(entry, stall_type)?;
 
         let path = entry.path();
 
@@ -175,30 +489,91 @@ impl<'a> PsiFdBuilder<'a> {
         {
             Ok(f) => f,
             Err(e) if e.kind() == io::ErrorKind::NotFound => {
-                return Err(PsiFdBuilderError::NoPsiEntry(path.into_owned()));
+                return Err(missing_entry(entry, path.into_owned()));
+            }
+            // Each trigger holds its own fd, see `TriggerUsage`
+            Err(e) if matches!(e.raw_os_error(), Some(EMFILE | ENFILE)) => {
+                return Err(PsiFdBuilderError::TriggerLimitReached(path.into_owned()));
+            }
+            Err(e) if is_rejection(&e) => {
+                return Err(PsiFdBuilderError::TriggerRejected(path.into_owned(), e));
             }
             Err(e) => {
                 return Err(e)?;
             }
         };
 
-        write_trigger(&mut file, stall_type, stall_amount, time_window)?;
+        write_trigger(&mut file, &spec).map_err(|e| {
+            if is_rejection(&e) {
+                PsiFdBuilderError::TriggerRejected(path.into_owned(), e)
+            } else {
+                e.into()
+            }
+        })?;
 
         let fd = OwnedFd::from(file);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(fd = fd.as_raw_fd(), trigger = %spec, "registered psi trigger");
 
         // SAFETY:
         // The trigger has been validated and registered
-        Ok(unsafe { PsiFd::new_unchecked(fd) })
+        Ok(unsafe { PsiFd::new_unchecked(fd) }.with_trigger(spec))
     }
 }
 
-#[inline(always)]
-fn write_trigger<W: Write>(
-    mut writer: W,
+/// Rejects stall types that are not tracked for an entry.
+pub(crate) fn check_stall_type(
+    entry: PsiEntry<'_>,
     stall_type: StallType,
-    stall_amount: Duration,
-    time_window: Duration,
-) -> io::Result<()> {
+) -> Result<(), PsiFdBuilderError> {
+    match (entry, stall_type) {
+        // The kernel rejects this with a bare EINVAL on write
+        (PsiEntry::Global(GlobalEntryType::Irq), StallType::Some) => {
+            Err(PsiFdBuilderError::IrqSomeUnsupported)
+        }
+        // Accepted since 5.13, but the system-wide full cpu line is
+        // always zero, so the trigger would silently never fire
+        (PsiEntry::Global(GlobalEntryType::Cpu), StallType::Full) => {
+            Err(PsiFdBuilderError::FullCpuUnsupported)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether the error means the entry is readable but refuses triggers,
+/// such as a read-only `/proc/pressure` on hardened kernels.
+fn is_rejection(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::PermissionDenied || e.raw_os_error() == Some(EROFS)
+}
+
+pub(crate) fn missing_entry(entry: PsiEntry<'_>, path: PathBuf) -> PsiFdBuilderError {
+    match entry {
+        // A missing /proc/pressure means psi is disabled altogether,
+        // in which case the irq entry is not the one to blame.
+        PsiEntry::Global(GlobalEntryType::Irq) if Path::new("/proc/pressure").is_dir() => {
+            if let Ok(version) = KernelVersion::current() {
+                return PsiFdBuilderError::IrqUnsupported(version);
+            }
+        }
+        PsiEntry::Cgroup(_, base) => {
+            let fs_type = cgroup::fs_type_of(base).ok().flatten();
+            if fs_type.as_deref() == Some("cgroup") {
+                return PsiFdBuilderError::CgroupV1(base.to_path_buf());
+            }
+        }
+        _ => {}
+    }
+    PsiFdBuilderError::NoPsiEntry(path)
+}
+
+/// Writes the trigger to an opened PSI file.
+///
+/// This is what [`PsiFdBuilder::build`] does after opening the
+/// [`PsiEntry`], for callers that obtain the file by other means.
+/// The whole trigger is written with a single `write` call,
+/// as the kernel requires.
+#[inline]
+pub fn write_trigger<W: Write>(mut writer: W, spec: &TriggerSpec) -> io::Result<()> {
     // "<full|some> 10000000 10000000\n" = 23 bytes
     const PSI_TRIGGER_BUF_SIZE: usize = 24;
 
@@ -207,13 +582,7 @@ fn write_trigger<W: Write>(
     let len = {
         let mut cursor = Cursor::new(&mut buf[..]);
 
-        writeln!(
-            cursor,
-            "{} {} {}",
-            stall_type,
-            stall_amount.as_micros(),
-            time_window.as_micros(),
-        )?;
+        writeln!(cursor, "{spec}")?;
 
         cursor.position() as usize
     };
@@ -223,7 +592,7 @@ fn write_trigger<W: Write>(
 
 #[cfg(test)]
 mod tests {
-    use std::{assert_matches, path::Path, time::Duration};
+    use std::{assert_matches, env, path::Path, process, time::Duration};
 
     use super::*;
     use crate::{CgroupEntryType, GlobalEntryType, PsiEntry};
@@ -338,6 +707,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn build_rejects_some_irq() {
+        assert_matches!(
+            PsiFd::builder()
+                .entry(PsiEntry::Global(GlobalEntryType::Irq))
+                .stall_type(StallType::Some)
+                .stall_amount(ONE_MICRO_SEC)
+                .time_window(ONE_SEC)
+                .build(),
+            Err(PsiFdBuilderError::IrqSomeUnsupported),
+        );
+    }
+
+    #[test]
+    fn build_rejects_full_global_cpu() {
+        assert_matches!(
+            PsiFd::builder()
+                .entry(PsiEntry::Global(GlobalEntryType::Cpu))
+                .stall_type(StallType::Full)
+                .stall_amount(ONE_MICRO_SEC)
+                .time_window(ONE_SEC)
+                .build(),
+            Err(PsiFdBuilderError::FullCpuUnsupported),
+        );
+    }
+
+    #[test]
+    fn rejection_errors() {
+        assert!(is_rejection(&io::ErrorKind::PermissionDenied.into()));
+        assert!(is_rejection(&io::Error::from_raw_os_error(EROFS)));
+        assert!(!is_rejection(&io::Error::from_raw_os_error(EMFILE)));
+    }
+
     #[test]
     fn build_reports_missing_psi_entry() {
         assert_matches!(
@@ -351,11 +753,179 @@ mod tests {
         );
     }
 
+    #[test]
+    fn missing_cgroup_entry_reports_cgroup_v1() {
+        let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").unwrap();
+        let Some(v1) = cgroup::mounts(&mountinfo).find(|mount| mount.fs_type == "cgroup") else {
+            return;
+        };
+
+        assert_matches!(
+            PsiFd::builder()
+                .entry(PsiEntry::Cgroup(CgroupEntryType::Memory, &v1.mount_point))
+                .stall_type(StallType::Some)
+                .stall_amount(ONE_MICRO_SEC)
+                .time_window(ONE_SEC)
+                .build(),
+            Err(PsiFdBuilderError::CgroupV1(_))
+        );
+    }
+
+    #[test]
+    fn missing_irq_entry_reports_kernel_version() {
+        let path = PathBuf::from("/proc/pressure/irq");
+        let err = missing_entry(PsiEntry::Global(GlobalEntryType::Irq), path);
+
+        if Path::new("/proc/pressure").is_dir() {
+            assert_matches!(err, PsiFdBuilderError::IrqUnsupported(_));
+        } else {
+            assert_matches!(err, PsiFdBuilderError::NoPsiEntry(_));
+        }
+    }
+
+    #[test]
+    fn psi_path_detection() {
+        assert!(is_psi_path(Path::new("/proc/pressure/cpu")));
+        assert!(is_psi_path(Path::new("/sys/fs/cgroup/memory.pressure")));
+        assert!(!is_psi_path(Path::new("/proc/stat")));
+        assert!(!is_psi_path(Path::new("/sys/fs/cgroup/memory.max")));
+    }
+
+    #[test]
+    fn try_from_rejects_non_psi_fd() {
+        let fd = OwnedFd::from(fs::File::open("/proc/self/stat").unwrap());
+
+        assert_eq!(
+            PsiFd::try_from(fd).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn try_from_rejects_pressure_name_outside_cgroup2() {
+        let path = env::temp_dir().join(format!("presutaoru-{}.pressure", process::id()));
+        fs::write(&path, "some avg10=0.00 avg60=0.00 avg300=0.00 total=0\n").unwrap();
+        let fd = OwnedFd::from(fs::File::open(&path).unwrap());
+        let result = PsiFd::try_from(fd);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn trigger_introspection() {
+        let fd = OwnedFd::from(fs::File::open("/proc/pressure/cpu").unwrap());
+        let psi_fd = PsiFd::try_from(fd).unwrap();
+        let spec = TriggerSpec::new(StallType::Some, ONE_MICRO_SEC, ONE_SEC).unwrap();
+
+        assert_eq!(psi_fd.trigger(), None);
+        assert_eq!(psi_fd.path().unwrap(), Path::new("/proc/pressure/cpu"));
+        assert_eq!(psi_fd.with_trigger(spec).trigger(), Some(spec));
+    }
+
+    #[test]
+    fn trigger_spec_display() {
+        let spec = TriggerSpec::new(StallType::Full, ONE_MICRO_SEC * 150, ONE_SEC).unwrap();
+
+        assert_eq!(spec.to_string(), "full 150 1000000");
+    }
+
+    #[test]
+    fn trigger_spec_truncates_to_micros() {
+        let spec = TriggerSpec::new(
+            StallType::Some,
+            ONE_MICRO_SEC * 150 + ONE_NANO_SEC * 999,
+            ONE_SEC + ONE_NANO_SEC,
+        )
+        .unwrap();
+
+        assert_eq!(spec.stall_amount(), ONE_MICRO_SEC * 150);
+        assert_eq!(spec.time_window(), ONE_SEC);
+    }
+
+    #[test]
+    fn strict_rejects_sub_microsecond_precision() {
+        assert_matches!(
+            PsiFd::builder()
+                .entry(PsiEntry::Global(GlobalEntryType::Memory))
+                .stall_type(StallType::Some)
+                .stall_amount(ONE_MICRO_SEC + ONE_NANO_SEC)
+                .time_window(ONE_SEC)
+                .strict(true)
+                .build(),
+            Err(PsiFdBuilderError::SubMicrosecondPrecision),
+        );
+    }
+
+    #[test]
+    fn round_window_for_unprivileged() {
+        let cases = [
+            (Duration::from_millis(500), Duration::from_secs(2)),
+            (Duration::from_secs(2), Duration::from_secs(2)),
+            (Duration::from_millis(2001), Duration::from_secs(4)),
+            (Duration::from_secs(10), Duration::from_secs(10)),
+        ];
+
+        for (window, expected) in cases {
+            let spec = TriggerSpec::new(StallType::Full, ONE_MICRO_SEC, window)
+                .unwrap()
+                .round_for_unprivileged();
+            assert_eq!(spec.time_window(), expected);
+            assert_eq!(spec.stall_amount(), ONE_MICRO_SEC);
+        }
+    }
+
+    #[test]
+    fn trigger_spec_round_trip() {
+        let spec = TriggerSpec::new(StallType::Some, ONE_MICRO_SEC * 150, ONE_SEC).unwrap();
+
+        assert_eq!(spec.to_string().parse::<TriggerSpec>().unwrap(), spec);
+        assert_eq!("some 150 1000000\n".parse::<TriggerSpec>().unwrap(), spec);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn trigger_spec_serde() {
+        let spec = TriggerSpec::new(StallType::Full, ONE_MICRO_SEC * 150, ONE_SEC).unwrap();
+
+        let json = serde_json::to_string(&spec).unwrap();
+        assert_eq!(json, r#""full 150 1000000""#);
+        assert_eq!(serde_json::from_str::<TriggerSpec>(&json).unwrap(), spec);
+        assert!(serde_json::from_str::<TriggerSpec>(r#""some 1 1""#).is_err());
+        assert_eq!(
+            serde_json::from_str::<StallType>(r#""some""#).unwrap(),
+            StallType::Some
+        );
+    }
+
+    #[test]
+    fn trigger_spec_parse_errors() {
+        for input in [
+            "",
+            "most 1 1000000",
+            "some 1",
+            "some x 1000000",
+            "some 1 1000000 1",
+        ] {
+            assert_matches!(
+                input.parse::<TriggerSpec>(),
+                Err(TriggerSpecParseError::Malformed(_))
+            );
+        }
+        assert_matches!(
+            "some 1 1".parse::<TriggerSpec>(),
+            Err(TriggerSpecParseError::Invalid(
+                PsiFdBuilderError::TimeWindowTooSmall
+            ))
+        );
+    }
+
     #[test]
     fn write_trigger() {
         let mut buf = [0u8; _];
 
-        super::write_trigger(buf.as_mut(), StallType::Some, ONE_MICRO_SEC, ONE_SEC).unwrap();
+        let spec = TriggerSpec::new(StallType::Some, ONE_MICRO_SEC, ONE_SEC).unwrap();
+        super::write_trigger(buf.as_mut(), &spec).unwrap();
 
         assert_eq!(&buf, b"some 1 1000000\n");
     }
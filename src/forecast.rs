@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+/// Projects a pressure average forward with double exponential smoothing.
+///
+/// Both the level and the rate of change of the series are smoothed, so
+/// a steadily climbing `avg10` is extrapolated at its current rate. This
+/// lets mitigation start before a hard trigger fires.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use presutaoru::*;
+/// let mut forecaster = Forecaster::new();
+/// for secs in 0..10 {
+///     forecaster.observe(Duration::from_secs(secs), secs as f32);
+/// }
+///
+/// let eta = forecaster.time_to(40.0).unwrap();
+/// if eta <= Duration::from_secs(60) {
+///     eprintln!("memory pressure will cross 40% in ~{}s", eta.as_secs());
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Forecaster {
+    alpha: f32,
+    beta: f32,
+    level: f32,
+    slope: f32,
+    last: Option<Duration>,
+}
+
+impl Forecaster {
+    /// Creates a forecaster with smoothing factors of 0.5 for the level
+    /// and 0.3 for the rate of change.
+    pub fn new() -> Self {
+        Self {
+            alpha: 0.5,
+            beta: 0.3,
+            level: 0.0,
+            slope: 0.0,
+            last: None,
+        }
+    }
+
+    /// Sets the weight of each new sample in the level.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` is not within `(0, 1]`.
+    pub fn alpha(mut self, alpha: f32) -> Self {
+        assert!(alpha > 0.0 && alpha <= 1.0, "alpha must be within (0, 1]");
+        self.alpha = alpha;
+        self
+    }
+
+    /// Sets the weight of each new sample in the rate of change.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `beta` is not within `(0, 1]`.
+    pub fn beta(mut self, beta: f32) -> Self {
+        assert!(beta > 0.0 && beta <= 1.0, "beta must be within (0, 1]");
+        self.beta = beta;
+        self
+    }
+
+    /// Learns a sample taken at `at`, measured from any fixed point
+    /// such as the start of monitoring.
+    ///
+    /// Samples taken before the previous one are ignored.
+    pub fn observe(&mut self, at: Duration, value: f32) {
+        let Some(last) = self.last else {
+            self.level = value;
+            self.last = Some(at);
+            return;
+        };
+        let Some(elapsed) = at.checked_sub(last) else {
+            return;
+        };
+        let dt = elapsed.as_secs_f32();
+
+        let level = self.alpha * value + (1.0 - self.alpha) * (self.level + self.slope * dt);
+        if dt > 0.0 {
+            self.slope = self.beta * (level - self.level) / dt + (1.0 - self.beta) * self.slope;
+        }
+        self.level = level;
+        self.last = Some(at);
+    }
+
+    /// Returns the projected value `horizon` after the last sample,
+    /// or `None` before any sample was observed.
+    pub fn forecast(&self, horizon: Duration) -> Option<f32> {
+        self.last?;
+        Some(self.level + self.slope * horizon.as_secs_f32())
+    }
+
+    /// Returns how long after the last sample the series is projected
+    /// to reach `threshold` at the current rate.
+    ///
+    /// Returns [`Duration::ZERO`] if it is already reached, and `None`
+    /// if it is not approaching or no sample was observed.
+    pub fn time_to(&self, threshold: f32) -> Option<Duration> {
+        self.last?;
+        if self.level >= threshold {
+            return Some(Duration::ZERO);
+        }
+        if self.slope <= 0.0 {
+            return None;
+        }
+        Duration::try_from_secs_f32((threshold - self.level) / self.slope).ok()
+    }
+
+    /// Returns the smoothed rate of change per second.
+    pub fn slope(&self) -> f32 {
+        self.slope
+    }
+}
+
+impl Default for Forecaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observe_linear(forecaster: &mut Forecaster, rate: f32) {
+        for secs in 0..30 {
+            forecaster.observe(Duration::from_secs(secs), 5.0 + rate * secs as f32);
+        }
+    }
+
+    #[test]
+    fn follows_linear_growth() {
+        let mut forecaster = Forecaster::new();
+        observe_linear(&mut forecaster, 1.0);
+
+        assert!((forecaster.slope() - 1.0).abs() < 0.01);
+        let projected = forecaster.forecast(Duration::from_secs(10)).unwrap();
+        assert!((projected - 44.0).abs() < 0.5);
+
+        let eta = forecaster.time_to(64.0).unwrap();
+        assert!((eta.as_secs_f32() - 30.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn falling_series_never_crosses() {
+        let mut forecaster = Forecaster::new();
+        observe_linear(&mut forecaster, -0.1);
+
+        assert!(forecaster.slope() < 0.0);
+        assert_eq!(forecaster.time_to(50.0), None);
+        assert_eq!(forecaster.time_to(1.0), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn empty_forecaster() {
+        let forecaster = Forecaster::new();
+
+        assert_eq!(forecaster.forecast(Duration::from_secs(1)), None);
+        assert_eq!(forecaster.time_to(0.0), None);
+    }
+
+    #[test]
+    fn ignores_out_of_order_samples() {
+        let mut forecaster = Forecaster::new();
+        forecaster.observe(Duration::from_secs(5), 10.0);
+        forecaster.observe(Duration::from_secs(4), 90.0);
+
+        assert_eq!(forecaster.forecast(Duration::ZERO), Some(10.0));
+    }
+}
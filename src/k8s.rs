@@ -0,0 +1,226 @@
+use std::path::{Path, PathBuf};
+
+use crate::Cgroup;
+
+/// Cgroup driver configured for the kubelet and container runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CgroupDriver {
+    /// `kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod<uid>.slice`
+    Systemd,
+    /// `kubepods/burstable/pod<uid>`
+    Cgroupfs,
+}
+
+/// QoS class of a pod, as reported in `status.qosClass`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PodQosClass {
+    Guaranteed,
+    Burstable,
+    BestEffort,
+}
+
+impl PodQosClass {
+    fn dir(&self) -> Option<&'static str> {
+        match self {
+            Self::Guaranteed => None,
+            Self::Burstable => Some("burstable"),
+            Self::BestEffort => Some("besteffort"),
+        }
+    }
+}
+
+/// Locates the cgroups the kubelet creates for a pod.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use presutaoru::*;
+/// let pod = PodCgroup::new(
+///     CgroupDriver::Systemd,
+///     PodQosClass::Burstable,
+///     "9b1c0a6e-3f2d-4c59-8e7a-2d3f4b5c6d7e",
+/// );
+/// let cgroup = pod.pod();
+///
+/// let psi_fd = PsiFd::builder()
+///     .entry(cgroup.entry(CgroupEntryType::Memory))
+///     .stall_type(StallType::Full)
+///     .stall_amount(Duration::from_millis(100))
+///     .time_window(Duration::from_secs(1))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct PodCgroup<'a> {
+    root: &'a Path,
+    driver: CgroupDriver,
+    qos: PodQosClass,
+    uid: &'a str,
+}
+
+impl<'a> PodCgroup<'a> {
+    const ROOT: &'static str = "/sys/fs/cgroup";
+
+    /// Creates a new [`PodCgroup`] for the pod with the given `metadata.uid`.
+    pub fn new(driver: CgroupDriver, qos: PodQosClass, uid: &'a str) -> Self {
+        Self {
+            root: Path::new(Self::ROOT),
+            driver,
+            qos,
+            uid,
+        }
+    }
+
+    /// Sets the directory the kubelet's `--cgroup-root` resolves to.
+    ///
+    /// Defaults to `/sys/fs/cgroup`.
+    pub fn root(mut self, root: &'a Path) -> Self {
+        self.root = root;
+        self
+    }
+
+    /// Returns the cgroup of the pod.
+    pub fn pod(&self) -> Cgroup {
+        let mut path = self.root.to_path_buf();
+        match self.driver {
+            CgroupDriver::Systemd => {
+                let uid = self.uid.replace('-', "_");
+                path.push("kubepods.slice");
+                match self.qos.dir() {
+                    Some(qos) => {
+                        path.push(format!("kubepods-{qos}.slice"));
+                        path.push(format!("kubepods-{qos}-pod{uid}.slice"));
+                    }
+                    None => path.push(format!("kubepods-pod{uid}.slice")),
+                }
+            }
+            CgroupDriver::Cgroupfs => {
+                path.push("kubepods");
+                if let Some(qos) = self.qos.dir() {
+                    path.push(qos);
+                }
+                path.push(format!("pod{}", self.uid));
+            }
+        }
+        Cgroup::new(path)
+    }
+
+    /// Returns the cgroup of a container in the pod.
+    ///
+    /// `container_id` is taken from `status.containerStatuses[].containerID`,
+    /// e.g. `containerd://<id>`. A bare id is assumed to be from containerd.
+    /// Returns `None` for runtimes other than containerd, CRI-O and docker.
+    pub fn container(&self, container_id: &str) -> Option<Cgroup> {
+        let (runtime, id) = container_id
+            .split_once("://")
+            .unwrap_or(("containerd", container_id));
+
+        let dir = match (self.driver, runtime) {
+            (CgroupDriver::Systemd, "containerd") => format!("cri-containerd-{id}.scope"),
+            (CgroupDriver::Systemd, "cri-o") => format!("crio-{id}.scope"),
+            (CgroupDriver::Systemd, "docker") => format!("docker-{id}.scope"),
+            (CgroupDriver::Cgroupfs, "containerd" | "docker") => id.to_owned(),
+            (CgroupDriver::Cgroupfs, "cri-o") => format!("crio-{id}"),
+            _ => return None,
+        };
+
+        let mut path = PathBuf::from(self.pod().path());
+        path.push(dir);
+        Some(Cgroup::new(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UID: &str = "9b1c0a6e-3f2d-4c59-8e7a-2d3f4b5c6d7e";
+
+    #[test]
+    fn systemd_pod_path() {
+        let cases = [
+            (
+                PodQosClass::Guaranteed,
+                "/sys/fs/cgroup/kubepods.slice/\
+                 kubepods-pod9b1c0a6e_3f2d_4c59_8e7a_2d3f4b5c6d7e.slice",
+            ),
+            (
+                PodQosClass::Burstable,
+                "/sys/fs/cgroup/kubepods.slice/kubepods-burstable.slice/\
+                 kubepods-burstable-pod9b1c0a6e_3f2d_4c59_8e7a_2d3f4b5c6d7e.slice",
+            ),
+            (
+                PodQosClass::BestEffort,
+                "/sys/fs/cgroup/kubepods.slice/kubepods-besteffort.slice/\
+                 kubepods-besteffort-pod9b1c0a6e_3f2d_4c59_8e7a_2d3f4b5c6d7e.slice",
+            ),
+        ];
+
+        for (qos, expected) in cases {
+            let pod = PodCgroup::new(CgroupDriver::Systemd, qos, UID);
+            assert_eq!(pod.pod().path(), Path::new(expected));
+        }
+    }
+
+    #[test]
+    fn cgroupfs_pod_path() {
+        let cases = [
+            (
+                PodQosClass::Guaranteed,
+                "/sys/fs/cgroup/kubepods/pod9b1c0a6e-3f2d-4c59-8e7a-2d3f4b5c6d7e",
+            ),
+            (
+                PodQosClass::Burstable,
+                "/sys/fs/cgroup/kubepods/burstable/pod9b1c0a6e-3f2d-4c59-8e7a-2d3f4b5c6d7e",
+            ),
+            (
+                PodQosClass::BestEffort,
+                "/sys/fs/cgroup/kubepods/besteffort/pod9b1c0a6e-3f2d-4c59-8e7a-2d3f4b5c6d7e",
+            ),
+        ];
+
+        for (qos, expected) in cases {
+            let pod = PodCgroup::new(CgroupDriver::Cgroupfs, qos, UID);
+            assert_eq!(pod.pod().path(), Path::new(expected));
+        }
+    }
+
+    #[test]
+    fn container_path() {
+        let systemd = PodCgroup::new(CgroupDriver::Systemd, PodQosClass::Guaranteed, UID)
+            .root(Path::new("/cg"));
+        let cgroupfs = PodCgroup::new(CgroupDriver::Cgroupfs, PodQosClass::Guaranteed, UID)
+            .root(Path::new("/cg"));
+        let pod_slice = "/cg/kubepods.slice/kubepods-pod9b1c0a6e_3f2d_4c59_8e7a_2d3f4b5c6d7e.slice";
+        let pod_dir = "/cg/kubepods/pod9b1c0a6e-3f2d-4c59-8e7a-2d3f4b5c6d7e";
+
+        let cases = [
+            (
+                systemd,
+                "containerd://abc",
+                format!("{pod_slice}/cri-containerd-abc.scope"),
+            ),
+            (
+                systemd,
+                "abc",
+                format!("{pod_slice}/cri-containerd-abc.scope"),
+            ),
+            (
+                systemd,
+                "cri-o://abc",
+                format!("{pod_slice}/crio-abc.scope"),
+            ),
+            (
+                systemd,
+                "docker://abc",
+                format!("{pod_slice}/docker-abc.scope"),
+            ),
+            (cgroupfs, "containerd://abc", format!("{pod_dir}/abc")),
+            (cgroupfs, "cri-o://abc", format!("{pod_dir}/crio-abc")),
+        ];
+
+        for (pod, id, expected) in cases {
+            assert_eq!(pod.container(id).unwrap().path(), Path::new(&expected));
+        }
+        assert!(systemd.container("rkt://abc").is_none());
+    }
+}
@@ -28,10 +28,65 @@
 #[cfg(not(any(target_os = "linux", target_os = "android")))]
 compile_error!("presutaoru only supports Linux and Android platforms.");
 
+mod anomaly;
+#[cfg(feature = "tokio")]
+mod async_fd;
+mod budget;
+mod capabilities;
+mod cgroup;
+mod debounce;
 mod entry;
+mod episode;
+mod escalation;
 mod fd;
+mod forecast;
+#[cfg(feature = "k8s")]
+mod k8s;
+mod maintenance;
+mod metrics;
+mod pid;
+mod sampler;
+#[cfg(feature = "scm-rights")]
+mod scm;
+mod state;
+mod stats;
+#[cfg(feature = "futures")]
+mod stream;
+mod systemd;
+mod trend;
+mod usage;
 
+#[cfg(feature = "tokio")]
+pub use crate::async_fd::PsiAsyncFd;
+#[cfg(feature = "k8s")]
+pub use crate::k8s::{CgroupDriver, PodCgroup, PodQosClass};
+#[cfg(feature = "scm-rights")]
+pub use crate::scm::{recv_psi_fd, send_psi_fd};
+#[cfg(feature = "futures")]
+pub use crate::stream::{PsiStream, Reactor, Registration};
 pub use crate::{
+    anomaly::{Anomaly, AnomalyDetector},
+    budget::StallBudget,
+    capabilities::{
+        Capabilities, CgroupHierarchy, KernelVersion, KernelVersionParseError, capabilities,
+    },
+    cgroup::{Cgroup, CgroupError},
+    debounce::Debouncer,
     entry::{CgroupEntryType, GlobalEntryType, PsiEntry},
-    fd::{PsiFd, PsiFdBuilder, PsiFdBuilderError, StallType},
+    episode::{EpisodeClassifier, EpisodeKind},
+    escalation::{Escalation, EscalationLevel},
+    fd::{
+        PsiFd, PsiFdBuilder, PsiFdBuilderError, StallType, TriggerSpec, TriggerSpecParseError,
+        write_trigger,
+    },
+    forecast::Forecaster,
+    maintenance::MaintenanceScheduler,
+    metrics::{MetricLabel, MetricsFormat, TextfileWriter},
+    pid::{PidPsiFd, PidPsiFdError},
+    sampler::PsiSampler,
+    state::{Edge, EdgeDetector},
+    stats::{PsiStats, PsiStatsParseError, StallStats},
+    systemd::SystemdPressureError,
+    trend::{Trend, TrendDirection},
+    usage::TriggerUsage,
 };
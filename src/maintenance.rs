@@ -0,0 +1,199 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Defers maintenance work such as compaction or GC while pressure is
+/// elevated, and releases it once pressure has been quiet for a while.
+///
+/// Tasks are opaque to the scheduler, e.g. closures or an enum of jobs.
+/// Report each trigger event with [`MaintenanceScheduler::pressure`] and
+/// regularly collect tasks that may run now with
+/// [`MaintenanceScheduler::take_due`]. A task that has waited for the
+/// maximum delay is released regardless of pressure, so that it is not
+/// starved on a host that never quiets down.
+///
+/// ```
+/// # use std::time::{Duration, Instant};
+/// # use presutaoru::*;
+/// let mut scheduler = MaintenanceScheduler::new(
+///     Duration::from_secs(30),
+///     Duration::from_secs(600),
+/// );
+/// let start = Instant::now();
+///
+/// scheduler.submit("compact", start);
+/// scheduler.pressure(start);
+/// assert!(scheduler.take_due(start + Duration::from_secs(10)).is_empty());
+/// assert_eq!(scheduler.take_due(start + Duration::from_secs(30)), ["compact"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MaintenanceScheduler<T> {
+    quiet_period: Duration,
+    max_delay: Duration,
+    last_pressure: Option<Instant>,
+    pending: VecDeque<(Instant, T)>,
+}
+
+impl<T> MaintenanceScheduler<T> {
+    /// Creates a scheduler that runs tasks after `quiet_period` without
+    /// pressure, or at the latest `max_delay` after their submission.
+    pub fn new(quiet_period: Duration, max_delay: Duration) -> Self {
+        Self {
+            quiet_period,
+            max_delay,
+            last_pressure: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Queues a task submitted at `now`.
+    pub fn submit(&mut self, task: T, now: Instant) {
+        self.pending.push_back((now, task));
+    }
+
+    /// Records that pressure was observed at `now`, e.g. a trigger fired.
+    pub fn pressure(&mut self, now: Instant) {
+        self.last_pressure = Some(self.last_pressure.map_or(now, |last| last.max(now)));
+    }
+
+    /// Returns whether pressure has been quiet for the quiet period at `now`.
+    pub fn is_quiet(&self, now: Instant) -> bool {
+        self.last_pressure.map_or(true, |last| {
+            now.saturating_duration_since(last) >= self.quiet_period
+        })
+    }
+
+    /// Removes and returns the tasks that may run at `now`, in
+    /// submission order.
+    ///
+    /// All tasks are due once pressure is quiet. Otherwise only those
+    /// that have waited for the maximum delay are.
+    pub fn take_due(&mut self, now: Instant) -> Vec<T> {
+        if self.is_quiet(now) {
+            return self.pending.drain(..).map(|(_, task)| task).collect();
+        }
+
+        let mut due = Vec::new();
+        while let Some((submitted, _)) = self.pending.front() {
+            if now.saturating_duration_since(*submitted) < self.max_delay {
+                break;
+            }
+            due.extend(self.pending.pop_front().map(|(_, task)| task));
+        }
+        due
+    }
+
+    /// Returns how long until [`MaintenanceScheduler::take_due`] may
+    /// release a task, assuming no further pressure.
+    ///
+    /// Returns `None` if no task is pending, or if neither deadline can
+    /// be represented, e.g. when both durations are [`Duration::MAX`].
+    pub fn next_due(&self, now: Instant) -> Option<Duration> {
+        let (submitted, _) = self.pending.front()?;
+        let starved = submitted.checked_add(self.max_delay);
+        let quiet = match self.last_pressure {
+            Some(last) => last.checked_add(self.quiet_period),
+            None => Some(now),
+        };
+        let due = starved.into_iter().chain(quiet).min()?;
+        Some(due.saturating_duration_since(now))
+    }
+
+    /// Returns the number of pending tasks.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns whether no task is pending.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUIET: Duration = Duration::from_secs(30);
+    const MAX_DELAY: Duration = Duration::from_secs(300);
+
+    const ONE_SEC: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn runs_immediately_without_pressure() {
+        let mut scheduler = MaintenanceScheduler::new(QUIET, MAX_DELAY);
+        let start = Instant::now();
+
+        scheduler.submit(1, start);
+        scheduler.submit(2, start);
+
+        assert_eq!(scheduler.next_due(start), Some(Duration::ZERO));
+        assert_eq!(scheduler.take_due(start), [1, 2]);
+        assert!(scheduler.is_empty());
+    }
+
+    #[test]
+    fn waits_for_quiet_period() {
+        let mut scheduler = MaintenanceScheduler::new(QUIET, MAX_DELAY);
+        let start = Instant::now();
+
+        scheduler.submit(1, start);
+        scheduler.pressure(start + ONE_SEC * 5);
+
+        assert!(scheduler.take_due(start + ONE_SEC * 20).is_empty());
+        assert_eq!(scheduler.next_due(start + ONE_SEC * 20), Some(ONE_SEC * 15));
+
+        scheduler.pressure(start + ONE_SEC * 30);
+        assert!(scheduler.take_due(start + ONE_SEC * 40).is_empty());
+        assert_eq!(scheduler.take_due(start + ONE_SEC * 60), [1]);
+    }
+
+    #[test]
+    fn releases_starved_tasks() {
+        let mut scheduler = MaintenanceScheduler::new(QUIET, MAX_DELAY);
+        let start = Instant::now();
+
+        scheduler.submit(1, start);
+        scheduler.submit(2, start + ONE_SEC * 100);
+        for n in (0..=300).step_by(10) {
+            scheduler.pressure(start + ONE_SEC * n);
+        }
+
+        assert_eq!(scheduler.take_due(start + ONE_SEC * 300), [1]);
+        assert_eq!(scheduler.len(), 1);
+        assert_eq!(
+            scheduler.next_due(start + ONE_SEC * 300),
+            Some(ONE_SEC * 30)
+        );
+    }
+
+    #[test]
+    fn out_of_order_pressure_is_ignored() {
+        let mut scheduler = MaintenanceScheduler::<()>::new(QUIET, MAX_DELAY);
+        let start = Instant::now();
+
+        scheduler.pressure(start + ONE_SEC * 20);
+        scheduler.pressure(start);
+
+        assert!(!scheduler.is_quiet(start + ONE_SEC * 40));
+        assert!(scheduler.is_quiet(start + ONE_SEC * 50));
+    }
+
+    #[test]
+    fn overflowing_delays() {
+        let mut scheduler = MaintenanceScheduler::new(ONE_SEC * 30, Duration::MAX);
+        let start = Instant::now();
+
+        scheduler.submit("compact", start);
+        scheduler.pressure(start);
+        assert_eq!(scheduler.next_due(start), Some(ONE_SEC * 30));
+
+        let mut scheduler = MaintenanceScheduler::new(Duration::MAX, Duration::MAX);
+        scheduler.submit("compact", start);
+        assert_eq!(scheduler.next_due(start), Some(Duration::ZERO));
+        scheduler.pressure(start);
+        assert_eq!(scheduler.next_due(start), None);
+        assert!(scheduler.take_due(start + ONE_SEC).is_empty());
+    }
+}
@@ -0,0 +1,433 @@
+use std::{
+    borrow::Cow,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{CgroupEntryType, GlobalEntryType, PsiEntry, PsiStats, StallStats, StallType};
+
+/// Writes [`PsiStats`] in the Prometheus text format for the
+/// node_exporter textfile collector.
+///
+/// Each write replaces the file atomically, so the collector never sees
+/// a partially written file. The file name must end in `.prom` to be
+/// picked up by the collector.
+///
+/// ```no_run
+/// # use std::{path::Path, thread, time::Duration};
+/// # use presutaoru::*;
+/// let writer = TextfileWriter::new("/var/lib/node_exporter/textfile/psi.prom");
+/// let cgroup = Path::new("/sys/fs/cgroup/system.slice");
+/// let entries = [
+///     PsiEntry::Global(GlobalEntryType::Memory),
+///     PsiEntry::Cgroup(CgroupEntryType::Memory, cgroup),
+/// ];
+///
+/// loop {
+///     let stats = entries.map(|entry| (entry, entry.read().unwrap()));
+///     writer.write(&stats).unwrap();
+///     thread::sleep(Duration::from_secs(15));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextfileWriter {
+    path: PathBuf,
+    format: MetricsFormat,
+}
+
+impl TextfileWriter {
+    /// Creates a writer for the given file.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            format: MetricsFormat::default(),
+        }
+    }
+
+    /// Sets the naming of the written metrics.
+    pub fn format(mut self, format: MetricsFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Returns the file written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Renders `stats` and replaces the file with them.
+    ///
+    /// The metrics are first written to a temporary file in the same
+    /// directory, which is then renamed over the target.
+    pub fn write(&self, stats: &[(PsiEntry<'_>, PsiStats)]) -> io::Result<()> {
+        let out = self.format.render(stats);
+
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, out)?;
+        fs::rename(&tmp, &self.path).map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            e
+        })
+    }
+}
+
+/// A label attached to every exported sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetricLabel {
+    /// `cpu`, `io`, `irq` or `memory`, named `resource` by default.
+    Resource,
+    /// `some` or `full`, named `type` by default.
+    StallType,
+    /// The cgroup directory of cgroup entries, named `cgroup` by default.
+    Cgroup,
+    /// `10s`, `60s` or `300s` on stall ratios, named `window` by default.
+    Window,
+}
+
+/// Names and labels of exported metrics.
+///
+/// By default, metrics are rendered as:
+///
+/// ```text
+/// # HELP psi_stall_ratio Share of time stalled over the averaging window.
+/// # TYPE psi_stall_ratio gauge
+/// psi_stall_ratio{resource="memory",type="some",window="10s"} 0.0012
+/// # HELP psi_stall_seconds_total Accumulated stall time.
+/// # TYPE psi_stall_seconds_total counter
+/// psi_stall_seconds_total{resource="memory",type="some"} 12.345678
+/// ```
+///
+/// Cgroup entries carry an additional `cgroup` label with the directory.
+/// Names are not validated and must follow the Prometheus data model.
+///
+/// ```
+/// # use presutaoru::*;
+/// let format = MetricsFormat::new()
+///     .namespace("node_pressure")
+///     .label(MetricLabel::Cgroup, "unit")
+///     .const_label("role", "database");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricsFormat {
+    namespace: String,
+    ratio_name: String,
+    total_name: String,
+    labels: [String; 4],
+    const_labels: Vec<(String, String)>,
+}
+
+impl MetricsFormat {
+    /// Creates the default format.
+    pub fn new() -> Self {
+        Self {
+            namespace: "psi".to_owned(),
+            ratio_name: "stall_ratio".to_owned(),
+            total_name: "stall_seconds_total".to_owned(),
+            labels: ["resource", "type", "cgroup", "window"].map(str::to_owned),
+            const_labels: Vec::new(),
+        }
+    }
+
+    /// Sets the prefix of all metric names, `psi` by default.
+    ///
+    /// An empty namespace leaves the names unprefixed.
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Sets the name of the stall ratio gauge, `stall_ratio` by default.
+    pub fn ratio_name(mut self, name: impl Into<String>) -> Self {
+        self.ratio_name = name.into();
+        self
+    }
+
+    /// Sets the name of the stall time counter, `stall_seconds_total`
+    /// by default.
+    pub fn total_name(mut self, name: impl Into<String>) -> Self {
+        self.total_name = name.into();
+        self
+    }
+
+    /// Renames a label.
+    pub fn label(mut self, label: MetricLabel, name: impl Into<String>) -> Self {
+        self.labels[label as usize] = name.into();
+        self
+    }
+
+    /// Adds a label with a fixed value to every sample, such as the host
+    /// or its role.
+    pub fn const_label(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.const_labels.push((name.into(), value.into()));
+        self
+    }
+
+    /// Renders `stats` in the Prometheus text format, e.g. as the body
+    /// of a scrape endpoint.
+    ///
+    /// Serve it with the content type `text/plain; version=0.0.4`.
+    ///
+    /// ```no_run
+    /// # use presutaoru::*;
+    /// let entry = PsiEntry::Global(GlobalEntryType::Memory);
+    /// let body = MetricsFormat::new().render(&[(entry, entry.read().unwrap())]);
+    /// ```
+    pub fn render(&self, stats: &[(PsiEntry<'_>, PsiStats)]) -> String {
+        let mut out = String::new();
+        self.encode(&mut out, stats)
+            .expect("writing to a String cannot fail");
+        out
+    }
+
+    pub(crate) fn encode(
+        &self,
+        out: &mut impl fmt::Write,
+        stats: &[(PsiEntry<'_>, PsiStats)],
+    ) -> fmt::Result {
+        let lines = || {
+            stats.iter().flat_map(|(entry, stats)| {
+                [StallType::Some, StallType::Full]
+                    .into_iter()
+                    .filter_map(move |stall_type| Some((entry, stall_type, stats.get(stall_type)?)))
+            })
+        };
+
+        let ratio = self.name(&self.ratio_name);
+        writeln!(
+            out,
+            "# HELP {ratio} Share of time stalled over the averaging window."
+        )?;
+        writeln!(out, "# TYPE {ratio} gauge")?;
+        for (entry, stall_type, line) in lines() {
+            for (window, avg) in averages(line) {
+                out.write_str(&ratio)?;
+                self.labels(out, entry, stall_type, Some(window))?;
+                writeln!(out, " {}", avg / 100.0)?;
+            }
+        }
+
+        let total = self.name(&self.total_name);
+        writeln!(out, "# HELP {total} Accumulated stall time.")?;
+        writeln!(out, "# TYPE {total} counter")?;
+        for (entry, stall_type, line) in lines() {
+            out.write_str(&total)?;
+            self.labels(out, entry, stall_type, None)?;
+            writeln!(out, " {}", line.total.as_secs_f64())?;
+        }
+
+        Ok(())
+    }
+
+    fn name(&self, name: &str) -> String {
+        if self.namespace.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}_{name}", self.namespace)
+        }
+    }
+
+    fn labels(
+        &self,
+        out: &mut impl fmt::Write,
+        entry: &PsiEntry<'_>,
+        stall_type: StallType,
+        window: Option<&str>,
+    ) -> fmt::Result {
+        let (resource, cgroup) = match entry {
+            PsiEntry::Global(entry_type) => (
+                Cow::Borrowed(match entry_type {
+                    GlobalEntryType::Cpu => "cpu",
+                    GlobalEntryType::Io => "io",
+                    GlobalEntryType::Irq => "irq",
+                    GlobalEntryType::Memory => "memory",
+                }),
+                None,
+            ),
+            PsiEntry::Cgroup(entry_type, dir) => (
+                Cow::Borrowed(match entry_type {
+                    CgroupEntryType::Cpu => "cpu",
+                    CgroupEntryType::Io => "io",
+                    CgroupEntryType::Memory => "memory",
+                }),
+                Some(dir.to_string_lossy()),
+            ),
+            // `<dir>/<resource>.pressure` is labelled like a cgroup entry
+            PsiEntry::Custom(path) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                match name.strip_suffix(".pressure") {
+                    Some(resource) => (
+                        Cow::Owned(resource.to_owned()),
+                        path.parent().map(Path::to_string_lossy),
+                    ),
+                    None => (name, None),
+                }
+            }
+        };
+
+        let [resource_label, type_label, cgroup_label, window_label] = &self.labels;
+        let mut labels = vec![
+            (resource_label.as_str(), resource),
+            (type_label.as_str(), stall_type.to_string().into()),
+        ];
+        if let Some(dir) = cgroup {
+            labels.push((cgroup_label, dir));
+        }
+        if let Some(window) = window {
+            labels.push((window_label, window.into()));
+        }
+        let const_labels = self
+            .const_labels
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str().into()));
+
+        out.write_char('{')?;
+        for (i, (name, value)) in labels.into_iter().chain(const_labels).enumerate() {
+            if i > 0 {
+                out.write_char(',')?;
+            }
+            write!(out, "{name}=\"")?;
+            escape(out, &value)?;
+            out.write_char('"')?;
+        }
+        out.write_char('}')
+    }
+}
+
+impl Default for MetricsFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn averages(line: &StallStats) -> [(&'static str, f32); 3] {
+    [
+        ("10s", line.avg10),
+        ("60s", line.avg60),
+        ("300s", line.avg300),
+    ]
+}
+
+/// Escapes a label value as required by the text format.
+fn escape(out: &mut impl fmt::Write, value: &str) -> fmt::Result {
+    for c in value.chars() {
+        match c {
+            '\\' => out.write_str("\\\\")?,
+            '"' => out.write_str("\\\"")?,
+            '\n' => out.write_str("\\n")?,
+            c => out.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn stats() -> PsiStats {
+        let line = StallStats {
+            avg10: 1.5,
+            avg60: 0.25,
+            avg300: 0.0,
+            total: Duration::from_micros(1_500_000),
+        };
+        PsiStats {
+            some: Some(line),
+            full: None,
+            unknown: Default::default(),
+        }
+    }
+
+    #[test]
+    fn encode_text_format() {
+        let cgroup = Path::new("/sys/fs/cgroup/a \"b\"");
+        let stats = [
+            (PsiEntry::Global(GlobalEntryType::Memory), stats()),
+            (PsiEntry::Cgroup(CgroupEntryType::Io, cgroup), stats()),
+        ];
+
+        let mut out = String::new();
+        MetricsFormat::new().encode(&mut out, &stats).unwrap();
+
+        assert_eq!(
+            out,
+            "\
+# HELP psi_stall_ratio Share of time stalled over the averaging window.
+# TYPE psi_stall_ratio gauge
+psi_stall_ratio{resource=\"memory\",type=\"some\",window=\"10s\"} 0.015
+psi_stall_ratio{resource=\"memory\",type=\"some\",window=\"60s\"} 0.0025
+psi_stall_ratio{resource=\"memory\",type=\"some\",window=\"300s\"} 0
+psi_stall_ratio{resource=\"io\",type=\"some\",cgroup=\"/sys/fs/cgroup/a \\\"b\\\"\",window=\"10s\"} 0.015
+psi_stall_ratio{resource=\"io\",type=\"some\",cgroup=\"/sys/fs/cgroup/a \\\"b\\\"\",window=\"60s\"} 0.0025
+psi_stall_ratio{resource=\"io\",type=\"some\",cgroup=\"/sys/fs/cgroup/a \\\"b\\\"\",window=\"300s\"} 0
+# HELP psi_stall_seconds_total Accumulated stall time.
+# TYPE psi_stall_seconds_total counter
+psi_stall_seconds_total{resource=\"memory\",type=\"some\"} 1.5
+psi_stall_seconds_total{resource=\"io\",type=\"some\",cgroup=\"/sys/fs/cgroup/a \\\"b\\\"\"} 1.5
+"
+        );
+    }
+
+    #[test]
+    fn encode_custom_format() {
+        let format = MetricsFormat::new()
+            .namespace("")
+            .ratio_name("pressure_ratio")
+            .total_name("pressure_seconds_total")
+            .label(MetricLabel::StallType, "kind")
+            .label(MetricLabel::Cgroup, "unit")
+            .const_label("host", "db-1");
+        let stats = [(
+            PsiEntry::Cgroup(CgroupEntryType::Cpu, Path::new("/cg")),
+            stats(),
+        )];
+
+        let mut out = String::new();
+        format.encode(&mut out, &stats).unwrap();
+
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines[1], "# TYPE pressure_ratio gauge");
+        assert_eq!(
+            lines[2],
+            "pressure_ratio{resource=\"cpu\",kind=\"some\",unit=\"/cg\",window=\"10s\",host=\"db-1\"} 0.015"
+        );
+        assert_eq!(
+            lines[7],
+            "pressure_seconds_total{resource=\"cpu\",kind=\"some\",unit=\"/cg\",host=\"db-1\"} 1.5"
+        );
+    }
+
+    #[test]
+    fn encode_custom_entry() {
+        let stats = [
+            (PsiEntry::Custom(Path::new("/cg/foo.pressure")), stats()),
+            (PsiEntry::Custom(Path::new("/proc/pressure/bar")), stats()),
+        ];
+
+        let mut out = String::new();
+        MetricsFormat::new().encode(&mut out, &stats).unwrap();
+
+        assert!(out.contains(
+            "psi_stall_seconds_total{resource=\"foo\",type=\"some\",cgroup=\"/cg\"} 1.5"
+        ));
+        assert!(out.contains("psi_stall_seconds_total{resource=\"bar\",type=\"some\"} 1.5"));
+    }
+
+    #[test]
+    fn write_replaces_file() {
+        let path = std::env::temp_dir().join(format!("presutaoru-{}.prom", std::process::id()));
+        let writer = TextfileWriter::new(&path);
+        let stats = [(PsiEntry::Global(GlobalEntryType::Cpu), stats())];
+
+        fs::write(&path, "stale").unwrap();
+        writer.write(&stats).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(contents.starts_with("# HELP psi_stall_ratio"));
+        assert!(contents.contains("psi_stall_seconds_total{resource=\"cpu\",type=\"some\"} 1.5"));
+    }
+}
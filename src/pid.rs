@@ -0,0 +1,123 @@
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+
+use crate::{Cgroup, CgroupEntryType, CgroupError, PsiFd, PsiFdBuilderError, TriggerSpec};
+
+/// A trigger on the cgroup of a process, able to follow the process
+/// when it migrates to another cgroup.
+///
+/// Migrations are not signalled by the kernel on the trigger fd, so
+/// callers poll [`PidPsiFd::refresh`] at an interval of their choosing.
+/// A relocation replaces the underlying fd, which must then be registered
+/// with epoll or the async runtime again.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use presutaoru::*;
+/// # let pid = 1;
+/// let spec = TriggerSpec::new(
+///     StallType::Full,
+///     Duration::from_millis(100),
+///     Duration::from_secs(1),
+/// )
+/// .unwrap();
+/// let mut psi_fd = PidPsiFd::new(pid, CgroupEntryType::Memory, spec).unwrap();
+///
+/// if let Some(previous) = psi_fd.refresh().unwrap() {
+///     eprintln!(
+///         "{pid} moved from {} to {}",
+///         previous.path().display(),
+///         psi_fd.cgroup().path().display(),
+///     );
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PidPsiFd {
+    pid: u32,
+    entry_type: CgroupEntryType,
+    spec: TriggerSpec,
+    cgroup: Cgroup,
+    fd: PsiFd,
+}
+
+/// Errors that can occur when creating or refreshing a [`PidPsiFd`]
+#[derive(thiserror::Error, Debug)]
+pub enum PidPsiFdError {
+    #[error("failed to resolve cgroup: {0}")]
+    Cgroup(#[from] CgroupError),
+    #[error("failed to build trigger: {0}")]
+    Build(#[from] PsiFdBuilderError),
+}
+
+impl PidPsiFd {
+    /// Registers the trigger on the current cgroup of `pid`.
+    pub fn new(
+        pid: u32,
+        entry_type: CgroupEntryType,
+        spec: TriggerSpec,
+    ) -> Result<Self, PidPsiFdError> {
+        let cgroup = Cgroup::of_pid(pid)?;
+        let fd = cgroup.trigger(entry_type, spec)?;
+
+        Ok(Self {
+            pid,
+            entry_type,
+            spec,
+            cgroup,
+            fd,
+        })
+    }
+
+    /// Re-resolves the cgroup of the process and re-arms the trigger
+    /// if it has moved.
+    ///
+    /// Returns the previous cgroup on relocation. On error the current
+    /// trigger is left untouched.
+    pub fn refresh(&mut self) -> Result<Option<Cgroup>, PidPsiFdError> {
+        let cgroup = Cgroup::of_pid(self.pid)?;
+        if cgroup == self.cgroup {
+            return Ok(None);
+        }
+
+        self.fd = cgroup.trigger(self.entry_type, self.spec)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            pid = self.pid,
+            from = %self.cgroup.path().display(),
+            to = %cgroup.path().display(),
+            "process moved, re-armed psi trigger"
+        );
+        Ok(Some(std::mem::replace(&mut self.cgroup, cgroup)))
+    }
+
+    /// Returns the pid being followed.
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Returns the cgroup the trigger is currently registered on.
+    pub fn cgroup(&self) -> &Cgroup {
+        &self.cgroup
+    }
+
+    /// Returns the current [`PsiFd`].
+    pub fn fd(&self) -> &PsiFd {
+        &self.fd
+    }
+
+    /// Returns the current [`PsiFd`], giving up on following the process.
+    pub fn into_fd(self) -> PsiFd {
+        self.fd
+    }
+}
+
+impl AsRawFd for PidPsiFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl AsFd for PidPsiFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
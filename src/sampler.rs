@@ -0,0 +1,166 @@
+use std::{fs::File, io, thread, time::Duration};
+
+use crate::{
+    PsiEntry, PsiFdBuilderError, PsiStats, StallType, TriggerSpec,
+    fd::{check_stall_type, missing_entry},
+    stats,
+};
+
+/// Polls a PSI file for when triggers are unavailable, such as in
+/// unprivileged containers on kernels before 6.5.
+///
+/// Reading a PSI file needs no privileges. The sampler reads it every
+/// interval and reports samples whose `avg10` is at or above a
+/// threshold, much like a trigger fires once per window while pressure
+/// lasts. Being based on a 10 second average, it reacts more slowly than
+/// a trigger.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use presutaoru::*;
+/// let mut sampler = PsiSampler::new(
+///     PsiEntry::Global(GlobalEntryType::Memory),
+///     StallType::Some,
+///     10.0,
+/// )
+/// .unwrap();
+///
+/// loop {
+///     let stats = sampler.wait().unwrap();
+///     eprintln!("memory pressure: {:?}", stats.some);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PsiSampler {
+    file: File,
+    stall_type: StallType,
+    threshold: f32,
+    interval: Duration,
+}
+
+impl PsiSampler {
+    /// Opens `entry` for sampling, reporting when the `avg10` of the
+    /// given [`StallType`] reaches `threshold` percent.
+    ///
+    /// Samples are taken every 2 seconds, the interval at which the
+    /// kernel updates the averages.
+    ///
+    /// Stall types that a trigger on `entry` could not use are rejected
+    /// the same way [`PsiFdBuilder::build`](crate::PsiFdBuilder::build)
+    /// rejects them, e.g. with [`PsiFdBuilderError::IrqSomeUnsupported`].
+    pub fn new(
+        entry: PsiEntry<'_>,
+        stall_type: StallType,
+        threshold: f32,
+    ) -> Result<Self, PsiFdBuilderError> {
+        check_stall_type(entry, stall_type)?;
+        let path = entry.path();
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(missing_entry(entry, path.into_owned()));
+            }
+            Err(e) => return Err(e)?,
+        };
+        Ok(Self {
+            file,
+            stall_type,
+            threshold,
+            interval: Duration::from_secs(2),
+        })
+    }
+
+    /// Opens `entry` for sampling in place of a trigger with the given
+    /// spec.
+    ///
+    /// The threshold is the share of the time window the trigger allows
+    /// to be stalled, and samples are taken once per window.
+    pub fn for_trigger(entry: PsiEntry<'_>, spec: &TriggerSpec) -> Result<Self, PsiFdBuilderError> {
+        let ratio = spec.stall_amount().as_secs_f32() / spec.time_window().as_secs_f32();
+        Ok(Self::new(entry, spec.stall_type(), ratio * 100.0)?.interval(spec.time_window()))
+    }
+
+    /// Sets how often the file is read.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Reads the file once and returns the stats if they are at or above
+    /// the threshold.
+    pub fn sample(&self) -> io::Result<Option<PsiStats>> {
+        let stats = stats::read_from(&self.file)?;
+        let above = stats
+            .get(self.stall_type)
+            .is_some_and(|line| line.avg10 >= self.threshold);
+        Ok(above.then_some(stats))
+    }
+
+    /// Blocks until a sample is at or above the threshold and returns it.
+    ///
+    /// The file is read every interval, starting one interval from now.
+    pub fn wait(&self) -> io::Result<PsiStats> {
+        loop {
+            thread::sleep(self.interval);
+            if let Some(stats) = self.sample()? {
+                return Ok(stats);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use crate::GlobalEntryType;
+
+    use super::*;
+
+    #[test]
+    fn sample_threshold() {
+        let entry = PsiEntry::Global(GlobalEntryType::Memory);
+
+        let always = PsiSampler::new(entry, StallType::Some, 0.0).unwrap();
+        assert!(always.sample().unwrap().unwrap().some.is_some());
+
+        let never = PsiSampler::new(entry, StallType::Some, f32::INFINITY).unwrap();
+        assert!(never.sample().unwrap().is_none());
+    }
+
+    #[test]
+    fn threshold_from_trigger() {
+        let spec = TriggerSpec::new(
+            StallType::Full,
+            Duration::from_millis(150),
+            Duration::from_secs(1),
+        )
+        .unwrap();
+        let sampler =
+            PsiSampler::for_trigger(PsiEntry::Global(GlobalEntryType::Io), &spec).unwrap();
+
+        assert_eq!(sampler.stall_type, StallType::Full);
+        assert!((sampler.threshold - 15.0).abs() < 1e-4);
+        assert_eq!(sampler.interval, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn rejects_untracked_stall_types() {
+        assert_matches!(
+            PsiSampler::new(
+                PsiEntry::Global(GlobalEntryType::Irq),
+                StallType::Some,
+                10.0
+            ),
+            Err(PsiFdBuilderError::IrqSomeUnsupported)
+        );
+        assert_matches!(
+            PsiSampler::new(
+                PsiEntry::Global(GlobalEntryType::Cpu),
+                StallType::Full,
+                10.0
+            ),
+            Err(PsiFdBuilderError::FullCpuUnsupported)
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    io, mem,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        unix::net::UnixStream,
+    },
+    ptr,
+};
+
+use crate::{PsiFd, TriggerSpec};
+
+// "<full|some> 10000000 10000000" = 22 bytes
+const PAYLOAD_BUF_SIZE: usize = 32;
+
+/// Control message buffer, aligned for `cmsghdr`.
+#[repr(C)]
+union CmsgBuf {
+    _align: libc::cmsghdr,
+    buf: [u8; 64],
+}
+
+impl CmsgBuf {
+    fn new() -> Self {
+        Self { buf: [0; 64] }
+    }
+}
+
+/// Sends a [`PsiFd`] and its [`TriggerSpec`] over a Unix socket.
+///
+/// The fd is passed with `SCM_RIGHTS`, so the receiving process gets
+/// its own descriptor for the same trigger. The trigger stays registered
+/// as long as any process holds a descriptor to it.
+///
+/// ```no_run
+/// # use std::{os::unix::net::UnixStream, time::Duration};
+/// # use presutaoru::*;
+/// let (broker, worker) = UnixStream::pair().unwrap();
+///
+/// // In the privileged broker
+/// let spec = TriggerSpec::new(
+///     StallType::Some,
+///     Duration::from_millis(150),
+///     Duration::from_secs(1),
+/// )
+/// .unwrap();
+/// let psi_fd = PsiFd::builder()
+///     .entry(PsiEntry::Global(GlobalEntryType::Memory))
+///     .stall_type(spec.stall_type())
+///     .stall_amount(spec.stall_amount())
+///     .time_window(spec.time_window())
+///     .build()
+///     .unwrap();
+/// send_psi_fd(&broker, &psi_fd, &spec).unwrap();
+///
+/// // In the unprivileged worker
+/// let (psi_fd, spec) = recv_psi_fd(&worker).unwrap();
+/// ```
+pub fn send_psi_fd(socket: &UnixStream, fd: &PsiFd, spec: &TriggerSpec) -> io::Result<()> {
+    let payload = spec.to_string();
+    let mut iov = libc::iovec {
+        iov_base: payload.as_ptr() as *mut _,
+        iov_len: payload.len(),
+    };
+    let mut cmsg = CmsgBuf::new();
+
+    // SAFETY:
+    // All pointers handed to the kernel point to live buffers of the
+    // given lengths, and the control buffer is large enough and aligned
+    // for a single cmsghdr carrying one fd.
+    let sent = unsafe {
+        let space = libc::CMSG_SPACE(mem::size_of::<RawFd>() as _);
+
+        let mut msg: libc::msghdr = mem::zeroed();
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = ptr::addr_of_mut!(cmsg).cast();
+        msg.msg_controllen = space as _;
+
+        let hdr = libc::CMSG_FIRSTHDR(&msg);
+        (*hdr).cmsg_level = libc::SOL_SOCKET;
+        (*hdr).cmsg_type = libc::SCM_RIGHTS;
+        (*hdr).cmsg_len = libc::CMSG_LEN(mem::size_of::<RawFd>() as _) as _;
+        ptr::write_unaligned(libc::CMSG_DATA(hdr).cast::<RawFd>(), fd.as_raw_fd());
+
+        libc::sendmsg(socket.as_raw_fd(), &msg, libc::MSG_NOSIGNAL)
+    };
+
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if sent as usize != payload.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::WriteZero,
+            "trigger spec was not sent in full",
+        ));
+    }
+    Ok(())
+}
+
+/// Receives a [`PsiFd`] and its [`TriggerSpec`] sent with [`send_psi_fd`].
+///
+/// The received fd is validated like [`PsiFd::try_from`], and carries
+/// the received spec as its [`PsiFd::trigger`].
+pub fn recv_psi_fd(socket: &UnixStream) -> io::Result<(PsiFd, TriggerSpec)> {
+    let mut payload = [0u8; PAYLOAD_BUF_SIZE];
+    let mut iov = libc::iovec {
+        iov_base: payload.as_mut_ptr().cast(),
+        iov_len: payload.len(),
+    };
+    let mut cmsg = CmsgBuf::new();
+
+    // SAFETY:
+    // See `send_psi_fd`. The kernel bounds `msg_controllen` by the size
+    // of the control buffer, so every header walked below lies within it.
+    let (len, fd) = unsafe {
+        let mut msg: libc::msghdr = mem::zeroed();
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = ptr::addr_of_mut!(cmsg).cast();
+        msg.msg_controllen = mem::size_of::<CmsgBuf>() as _;
+
+        let len = libc::recvmsg(socket.as_raw_fd(), &mut msg, libc::MSG_CMSG_CLOEXEC);
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut fd = None;
+        let mut hdr = libc::CMSG_FIRSTHDR(&msg);
+        while !hdr.is_null() {
+            if (*hdr).cmsg_level == libc::SOL_SOCKET && (*hdr).cmsg_type == libc::SCM_RIGHTS {
+                let data = libc::CMSG_DATA(hdr);
+                let count = ((*hdr).cmsg_len as usize - (data as usize - hdr as usize))
+                    / mem::size_of::<RawFd>();
+                for i in 0..count {
+                    let raw = ptr::read_unaligned(data.cast::<RawFd>().add(i));
+                    // Take ownership of every received fd so that extras get closed
+                    let owned = OwnedFd::from_raw_fd(raw);
+                    if fd.is_none() {
+                        fd = Some(owned);
+                    }
+                }
+            }
+            hdr = libc::CMSG_NXTHDR(&msg, hdr);
+        }
+
+        if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "control message truncated",
+            ));
+        }
+
+        (len as usize, fd)
+    };
+
+    if len == 0 && fd.is_none() {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let fd = fd.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "no fd received with trigger spec",
+        )
+    })?;
+    let spec = std::str::from_utf8(&payload[..len])
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    Ok((PsiFd::try_from(fd)?.with_trigger(spec), spec))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, time::Duration};
+
+    use super::*;
+    use crate::StallType;
+
+    #[test]
+    fn round_trip() {
+        let (tx, rx) = UnixStream::pair().unwrap();
+
+        // Reading a PSI file requires no privileges, unlike registering a trigger
+        let file = File::open("/proc/pressure/cpu").unwrap();
+        // SAFETY:
+        // Only the fd transfer is under test
+        let psi_fd = unsafe { PsiFd::new_unchecked(file.into()) };
+        let spec = TriggerSpec::new(
+            StallType::Some,
+            Duration::from_millis(150),
+            Duration::from_secs(1),
+        )
+        .unwrap();
+
+        send_psi_fd(&tx, &psi_fd, &spec).unwrap();
+        let (received, received_spec) = recv_psi_fd(&rx).unwrap();
+
+        assert_eq!(received_spec, spec);
+        assert_eq!(received.trigger(), Some(spec));
+        assert_ne!(received.as_raw_fd(), psi_fd.as_raw_fd());
+        assert!(received.read_stats().unwrap().some.is_some());
+    }
+
+    #[test]
+    fn recv_rejects_missing_fd() {
+        let (tx, rx) = UnixStream::pair().unwrap();
+
+        io::Write::write_all(&mut &tx, b"some 1 1000000").unwrap();
+
+        assert_eq!(
+            recv_psi_fd(&rx).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+/// Transition reported by [`EdgeDetector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Edge {
+    /// The trigger started firing.
+    Raised,
+    /// The trigger has not fired for the configured number of windows.
+    Cleared,
+}
+
+/// Turns raw trigger firings into rising and falling edges per key.
+///
+/// A trigger fires at most once per time window while pressure lasts,
+/// but never reports that pressure is gone. A key is raised on its
+/// first firing and cleared once it has not fired for a number of its
+/// windows. Keys identify the triggers, e.g. an index or the watched
+/// cgroup.
+///
+/// ```
+/// # use std::time::{Duration, Instant};
+/// # use presutaoru::*;
+/// let mut detector = EdgeDetector::new(3);
+/// let window = Duration::from_secs(2);
+/// let start = Instant::now();
+///
+/// assert_eq!(detector.fired("memory", window, start), Some(Edge::Raised));
+/// assert_eq!(detector.fired("memory", window, start + window), None);
+///
+/// assert!(detector.expire(start + Duration::from_secs(7)).is_empty());
+/// assert_eq!(detector.expire(start + Duration::from_secs(8)), ["memory"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct EdgeDetector<K> {
+    clear_after: u32,
+    raised: HashMap<K, (Instant, Duration)>,
+}
+
+impl<K: Eq + Hash> EdgeDetector<K> {
+    /// Creates a detector clearing keys after `clear_after` windows
+    /// without a firing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `clear_after` is zero.
+    pub fn new(clear_after: u32) -> Self {
+        assert!(clear_after > 0, "clear_after must not be zero");
+        Self {
+            clear_after,
+            raised: HashMap::new(),
+        }
+    }
+
+    /// Records a firing of `key`, whose trigger has the given time
+    /// window, at `now`.
+    ///
+    /// Returns [`Edge::Raised`] if the key was not raised yet.
+    pub fn fired(&mut self, key: K, window: Duration, now: Instant) -> Option<Edge> {
+        match self.raised.insert(key, (now, window)) {
+            Some(_) => None,
+            None => Some(Edge::Raised),
+        }
+    }
+
+    /// Clears and returns the keys that have been quiet for long enough
+    /// at `now`.
+    ///
+    /// Each returned key has transitioned with [`Edge::Cleared`]. Call
+    /// this when a firing is received and when
+    /// [`EdgeDetector::next_expiry`] elapses.
+    pub fn expire(&mut self, now: Instant) -> Vec<K>
+    where
+        K: Clone,
+    {
+        let clear_after = self.clear_after;
+        let cleared: Vec<K> = self
+            .raised
+            .iter()
+            .filter(|(_, (last, window))| {
+                // A quiet period too long to represent never elapses
+                window
+                    .checked_mul(clear_after)
+                    .is_some_and(|quiet| now.saturating_duration_since(*last) >= quiet)
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &cleared {
+            self.raised.remove(key);
+        }
+        cleared
+    }
+
+    /// Returns how long until the next raised key may be cleared,
+    /// assuming no further firing.
+    ///
+    /// Returns `None` if no key is raised, or if none of the raised keys
+    /// can ever be cleared because its quiet period overflows.
+    pub fn next_expiry(&self, now: Instant) -> Option<Duration> {
+        self.raised
+            .values()
+            .filter_map(|(last, window)| {
+                let expiry = last.checked_add(window.checked_mul(self.clear_after)?)?;
+                Some(expiry.saturating_duration_since(now))
+            })
+            .min()
+    }
+
+    /// Returns whether `key` is currently raised.
+    pub fn is_raised(&self, key: &K) -> bool {
+        self.raised.contains_key(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE_SEC: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn raises_once_until_cleared() {
+        let mut detector = EdgeDetector::new(2);
+        let start = Instant::now();
+
+        assert_eq!(detector.fired(1, ONE_SEC, start), Some(Edge::Raised));
+        assert_eq!(detector.fired(1, ONE_SEC, start + ONE_SEC), None);
+        assert!(detector.is_raised(&1));
+        assert_eq!(detector.next_expiry(start + ONE_SEC), Some(ONE_SEC * 2));
+
+        assert_eq!(detector.expire(start + ONE_SEC * 3), [1]);
+        assert!(!detector.is_raised(&1));
+        assert_eq!(detector.next_expiry(start + ONE_SEC * 3), None);
+
+        assert_eq!(
+            detector.fired(1, ONE_SEC, start + ONE_SEC * 4),
+            Some(Edge::Raised)
+        );
+    }
+
+    #[test]
+    fn keys_clear_by_their_own_window() {
+        let mut detector = EdgeDetector::new(3);
+        let start = Instant::now();
+
+        detector.fired("cpu", ONE_SEC, start);
+        detector.fired("memory", ONE_SEC * 5, start);
+
+        assert_eq!(detector.next_expiry(start), Some(ONE_SEC * 3));
+        assert_eq!(detector.expire(start + ONE_SEC * 3), ["cpu"]);
+        assert!(detector.expire(start + ONE_SEC * 14).is_empty());
+        assert_eq!(detector.expire(start + ONE_SEC * 15), ["memory"]);
+    }
+
+    #[test]
+    fn overflowing_windows_never_clear() {
+        let mut detector = EdgeDetector::new(u32::MAX);
+        let start = Instant::now();
+
+        detector.fired("cpu", Duration::MAX, start);
+        detector.fired("memory", ONE_SEC * 10, start);
+
+        assert!(detector.expire(start + ONE_SEC).is_empty());
+        assert_eq!(detector.next_expiry(start), Some(ONE_SEC * 10 * u32::MAX));
+
+        detector.expire(start + ONE_SEC * 10 * u32::MAX);
+        assert!(detector.is_raised(&"cpu"));
+        assert!(!detector.is_raised(&"memory"));
+        assert_eq!(detector.next_expiry(start), None);
+    }
+}
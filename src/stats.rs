@@ -0,0 +1,298 @@
+use std::{
+    collections::BTreeMap, fs::File, io, os::unix::fs::FileExt as _, str::FromStr, time::Duration,
+};
+
+use crate::StallType;
+
+/// Parsed contents of a PSI file.
+///
+/// ```console
+/// some avg10=0.00 avg60=0.00 avg300=0.00 total=0
+/// full avg10=0.00 avg60=0.00 avg300=0.00 total=0
+/// ```
+///
+/// Lines and fields not known to this crate are collected into
+/// [`PsiStats::unknown`] instead of failing the parse, so newer
+/// kernels keep working.
+///
+/// <https://docs.kernel.org/accounting/psi.html>
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PsiStats {
+    /// The `some` line, if reported.
+    ///
+    /// This is `None` for the irq file, which only reports `full`.
+    pub some: Option<StallStats>,
+    /// The `full` line, if reported.
+    pub full: Option<StallStats>,
+    /// Unrecognized fields keyed by `<line>.<field>`, e.g. `some.avg900`.
+    ///
+    /// Fields without a `=` are stored with an empty value.
+    pub unknown: BTreeMap<String, String>,
+}
+
+/// A single line of a PSI file.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StallStats {
+    /// Stall ratio in percent over the last 10 seconds.
+    pub avg10: f32,
+    /// Stall ratio in percent over the last 60 seconds.
+    pub avg60: f32,
+    /// Stall ratio in percent over the last 300 seconds.
+    pub avg300: f32,
+    /// Accumulated stall time.
+    pub total: Duration,
+}
+
+/// Errors that can occur when parsing [`PsiStats`]
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum PsiStatsParseError {
+    #[error("duplicate stall type {0}")]
+    DuplicateStallType(StallType),
+    #[error("missing field {0}")]
+    MissingField(&'static str),
+    #[error("invalid value for {0}")]
+    InvalidValue(&'static str),
+}
+
+impl PsiStats {
+    /// Returns the line for the given [`StallType`].
+    pub fn get(&self, stall_type: StallType) -> Option<&StallStats> {
+        match stall_type {
+            StallType::Some => self.some.as_ref(),
+            StallType::Full => self.full.as_ref(),
+        }
+    }
+
+    /// Returns how much the stall time of the given [`StallType`] grew
+    /// since an earlier read of the same file.
+    ///
+    /// This tells how much worse things got between two wakeups of a
+    /// trigger, independent of the averaging windows. Returns `None` if
+    /// either read lacks the line. A total that went backwards, e.g. on
+    /// a recreated cgroup, yields zero.
+    pub fn stalled_since(&self, previous: &PsiStats, stall_type: StallType) -> Option<Duration> {
+        let now = self.get(stall_type)?.total;
+        let then = previous.get(stall_type)?.total;
+        Some(now.saturating_sub(then))
+    }
+}
+
+impl FromStr for PsiStats {
+    type Err = PsiStatsParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut stats = PsiStats::default();
+
+        for line in s.lines() {
+            let mut fields = line.split_ascii_whitespace();
+            let Some(name) = fields.next() else {
+                continue;
+            };
+            let (slot, stall_type) = match name {
+                "some" => (&mut stats.some, StallType::Some),
+                "full" => (&mut stats.full, StallType::Full),
+                _ => {
+                    for field in fields {
+                        insert_unknown(&mut stats.unknown, name, field);
+                    }
+                    continue;
+                }
+            };
+            if slot.is_some() {
+                return Err(PsiStatsParseError::DuplicateStallType(stall_type));
+            }
+            *slot = Some(parse_fields(name, fields, &mut stats.unknown)?);
+        }
+
+        Ok(stats)
+    }
+}
+
+/// Reads and parses a PSI file from the beginning.
+pub(crate) fn read_from(file: &File) -> io::Result<PsiStats> {
+    // Two lines of "<some|full> avg10=100.00 avg60=100.00 avg300=100.00 total=<u64>\n",
+    // grown on demand in case future kernels report more
+    const PSI_STATS_BUF_SIZE: usize = 256;
+
+    let mut buf = vec![0u8; PSI_STATS_BUF_SIZE];
+    let mut len = 0;
+    loop {
+        if len == buf.len() {
+            buf.resize(len * 2, 0);
+        }
+        match file.read_at(&mut buf[len..], len as u64)? {
+            0 => break,
+            n => len += n,
+        }
+    }
+
+    std::str::from_utf8(&buf[..len])
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn insert_unknown(unknown: &mut BTreeMap<String, String>, line: &str, field: &str) {
+    let (key, value) = field.split_once('=').unwrap_or((field, ""));
+    unknown.insert(format!("{line}.{key}"), value.into());
+}
+
+fn parse_fields<'a>(
+    line: &str,
+    fields: impl Iterator<Item = &'a str>,
+    unknown: &mut BTreeMap<String, String>,
+) -> Result<StallStats, PsiStatsParseError> {
+    let mut avg10 = None;
+    let mut avg60 = None;
+    let mut avg300 = None;
+    let mut total = None;
+
+    for field in fields {
+        let Some((key, value)) = field.split_once('=') else {
+            insert_unknown(unknown, line, field);
+            continue;
+        };
+        match key {
+            "avg10" => avg10 = Some(parse_avg("avg10", value)?),
+            "avg60" => avg60 = Some(parse_avg("avg60", value)?),
+            "avg300" => avg300 = Some(parse_avg("avg300", value)?),
+            "total" => {
+                let us = value
+                    .parse()
+                    .map_err(|_| PsiStatsParseError::InvalidValue("total"))?;
+                total = Some(Duration::from_micros(us));
+            }
+            _ => insert_unknown(unknown, line, field),
+        }
+    }
+
+    Ok(StallStats {
+        avg10: avg10.ok_or(PsiStatsParseError::MissingField("avg10"))?,
+        avg60: avg60.ok_or(PsiStatsParseError::MissingField("avg60"))?,
+        avg300: avg300.ok_or(PsiStatsParseError::MissingField("avg300"))?,
+        total: total.ok_or(PsiStatsParseError::MissingField("total"))?,
+    })
+}
+
+fn parse_avg(name: &'static str, value: &str) -> Result<f32, PsiStatsParseError> {
+    value
+        .parse()
+        .map_err(|_| PsiStatsParseError::InvalidValue(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_some_and_full() {
+        let stats: PsiStats = "some avg10=1.50 avg60=0.25 avg300=0.00 total=123456\n\
+                               full avg10=0.00 avg60=0.00 avg300=0.00 total=42\n"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            stats.some,
+            Some(StallStats {
+                avg10: 1.5,
+                avg60: 0.25,
+                avg300: 0.0,
+                total: Duration::from_micros(123456),
+            })
+        );
+        assert_eq!(
+            stats.get(StallType::Full).unwrap().total,
+            Duration::from_micros(42)
+        );
+    }
+
+    #[test]
+    fn stalled_since_previous_read() {
+        let previous: PsiStats = "some avg10=0.00 avg60=0.00 avg300=0.00 total=1000\n"
+            .parse()
+            .unwrap();
+        let current: PsiStats = "some avg10=0.00 avg60=0.00 avg300=0.00 total=251000\n\
+                                 full avg10=0.00 avg60=0.00 avg300=0.00 total=0\n"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            current.stalled_since(&previous, StallType::Some),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(
+            previous.stalled_since(&current, StallType::Some),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(current.stalled_since(&previous, StallType::Full), None);
+    }
+
+    #[test]
+    fn parse_some_only() {
+        let stats: PsiStats = "some avg10=0.00 avg60=0.00 avg300=0.00 total=0\n"
+            .parse()
+            .unwrap();
+
+        assert!(stats.some.is_some());
+        assert!(stats.full.is_none());
+    }
+
+    #[test]
+    fn parse_full_only() {
+        let stats: PsiStats = "full avg10=0.00 avg60=0.00 avg300=0.00 total=0\n"
+            .parse()
+            .unwrap();
+
+        assert!(stats.some.is_none());
+        assert!(stats.full.is_some());
+    }
+
+    #[test]
+    fn parse_keeps_unknown_fields() {
+        let stats: PsiStats = "some avg10=0.00 avg60=0.00 avg300=0.00 total=0 avg900=1.00\n\
+                               full avg10=0.00 avg60=0.00 avg300=0.00 total=0 flag\n\
+                               peak avg10=2.00\n"
+            .parse()
+            .unwrap();
+
+        assert!(stats.some.is_some());
+        assert!(stats.full.is_some());
+        assert_eq!(
+            stats.unknown,
+            BTreeMap::from([
+                ("full.flag".into(), "".into()),
+                ("peak.avg10".into(), "2.00".into()),
+                ("some.avg900".into(), "1.00".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_rejects_malformed_input() {
+        let cases = [
+            (
+                "some avg10=0.00 avg60=0.00 total=0",
+                PsiStatsParseError::MissingField("avg300"),
+            ),
+            (
+                "some avg10=x avg60=0.00 avg300=0.00 total=0",
+                PsiStatsParseError::InvalidValue("avg10"),
+            ),
+            (
+                "some avg10=0.00 avg60=0.00 avg300=0.00 total=-1",
+                PsiStatsParseError::InvalidValue("total"),
+            ),
+            (
+                "some avg10=0.00 avg60=0.00 avg300=0.00 total=0\n\
+                 some avg10=0.00 avg60=0.00 avg300=0.00 total=0",
+                PsiStatsParseError::DuplicateStallType(StallType::Some),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(input.parse::<PsiStats>(), Err(expected));
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    io,
+    os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+
+use crate::PsiFd;
+
+/// An executor's reactor that can wait for priority readiness on an fd.
+///
+/// Triggers report events as `POLLPRI` / `EPOLLPRI`, so implementations
+/// must register for that, not for readability.
+pub trait Reactor {
+    /// Handle of an fd registered with the reactor.
+    type Registration: Registration;
+
+    /// Registers `fd` for priority readiness.
+    ///
+    /// The registration is dropped before the fd is closed.
+    fn register(&self, fd: RawFd) -> io::Result<Self::Registration>;
+}
+
+/// An fd registered with a [`Reactor`].
+pub trait Registration {
+    /// Polls for priority readiness, clearing it once reported.
+    ///
+    /// Once the kernel releases the trigger, e.g. because the watched
+    /// cgroup was removed, the fd reports `POLLERR` along with
+    /// `POLLPRI` on every poll. Implementations should report that as
+    /// an error of kind [`io::ErrorKind::NotFound`] instead of readiness.
+    fn poll_priority(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>>;
+}
+
+/// A [`PsiFd`] yielding an item each time the trigger fires, on any
+/// executor that implements [`Reactor`].
+///
+/// ```no_run
+/// # use std::{io, os::fd::RawFd, task::{Context, Poll}};
+/// # use presutaoru::*;
+/// struct MyReactor;
+/// struct MyRegistration;
+///
+/// impl Reactor for MyReactor {
+///     type Registration = MyRegistration;
+///
+///     fn register(&self, fd: RawFd) -> io::Result<MyRegistration> {
+///         // Add `fd` to the executor's epoll set with EPOLLPRI
+///         # Ok(MyRegistration)
+///     }
+/// }
+///
+/// impl Registration for MyRegistration {
+///     fn poll_priority(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+///         // Store the waker and wake it once EPOLLPRI is reported
+///         # Poll::Pending
+///     }
+/// }
+///
+/// # fn run(psi_fd: PsiFd) -> io::Result<()> {
+/// let stream = PsiStream::new(psi_fd, &MyReactor)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PsiStream<R: Reactor> {
+    // Declared first so that it is dropped before the fd is closed
+    registration: R::Registration,
+    fd: PsiFd,
+}
+
+impl<R: Reactor> PsiStream<R> {
+    /// Registers the [`PsiFd`] with `reactor`.
+    pub fn new(fd: PsiFd, reactor: &R) -> io::Result<Self> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(fd = fd.as_raw_fd(), "registering psi fd with reactor");
+        Ok(Self {
+            registration: reactor.register(fd.as_raw_fd())?,
+            fd,
+        })
+    }
+
+    /// Returns a shared reference to the inner [`PsiFd`].
+    pub fn get_ref(&self) -> &PsiFd {
+        &self.fd
+    }
+
+    /// Deregisters the fd and returns the inner [`PsiFd`].
+    pub fn into_inner(self) -> PsiFd {
+        self.fd
+    }
+}
+
+impl<R: Reactor> Stream for PsiStream<R>
+where
+    R::Registration: Unpin,
+{
+    type Item = io::Result<()>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let poll = this.registration.poll_priority(cx);
+        #[cfg(feature = "tracing")]
+        if let Poll::Ready(result) = &poll {
+            tracing::trace!(fd = this.fd.as_raw_fd(), ?result, "psi trigger fired");
+        }
+        poll.map(Some)
+    }
+}
+
+impl<R: Reactor> AsRawFd for PsiStream<R> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl<R: Reactor> AsFd for PsiStream<R> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::File,
+        os::fd::BorrowedFd,
+        ptr,
+        task::{RawWaker, RawWakerVTable, Waker},
+    };
+
+    use nix::poll::{PollFd, PollFlags, PollTimeout, poll};
+
+    use super::*;
+
+    /// Polls the fd directly instead of waiting on a reactor.
+    struct PollReactor;
+    struct PollRegistration(RawFd);
+
+    impl Reactor for PollReactor {
+        type Registration = PollRegistration;
+
+        fn register(&self, fd: RawFd) -> io::Result<PollRegistration> {
+            Ok(PollRegistration(fd))
+        }
+    }
+
+    impl Registration for PollRegistration {
+        fn poll_priority(&mut self, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            // SAFETY:
+            // The stream keeps the fd open while registered
+            let fd = unsafe { BorrowedFd::borrow_raw(self.0) };
+            let mut fds = [PollFd::new(fd, PollFlags::POLLPRI)];
+            poll(&mut fds, PollTimeout::ZERO)?;
+            match fds[0].revents() {
+                Some(revents) if revents.contains(PollFlags::POLLPRI) => Poll::Ready(Ok(())),
+                _ => Poll::Pending,
+            }
+        }
+    }
+
+    /// Reports the trigger as released by the kernel.
+    struct ReleasedReactor;
+    struct ReleasedRegistration;
+
+    impl Reactor for ReleasedReactor {
+        type Registration = ReleasedRegistration;
+
+        fn register(&self, _: RawFd) -> io::Result<ReleasedRegistration> {
+            Ok(ReleasedRegistration)
+        }
+    }
+
+    impl Registration for ReleasedRegistration {
+        fn poll_priority(&mut self, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Err(io::ErrorKind::NotFound.into()))
+        }
+    }
+
+    fn noop_waker() -> Waker {
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(
+            |_| RawWaker::new(ptr::null(), &VTABLE),
+            |_| {},
+            |_| {},
+            |_| {},
+        );
+        // SAFETY:
+        // The vtable functions ignore the data pointer
+        unsafe { Waker::from_raw(RawWaker::new(ptr::null(), &VTABLE)) }
+    }
+
+    #[test]
+    fn yields_on_priority_readiness() {
+        // PSI files without a trigger always report POLLPRI
+        let file = File::open("/proc/pressure/cpu").unwrap();
+        // SAFETY:
+        // Only the readiness plumbing is under test
+        let psi_fd = unsafe { PsiFd::new_unchecked(file.into()) };
+        let mut stream = PsiStream::new(psi_fd, &PollReactor).unwrap();
+
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let item = Pin::new(&mut stream).poll_next(&mut cx);
+
+        assert!(matches!(item, Poll::Ready(Some(Ok(())))));
+    }
+
+    #[test]
+    fn yields_released_trigger_error() {
+        let file = File::open("/proc/pressure/cpu").unwrap();
+        // SAFETY:
+        // Only the readiness plumbing is under test
+        let psi_fd = unsafe { PsiFd::new_unchecked(file.into()) };
+        let mut stream = PsiStream::new(psi_fd, &ReleasedReactor).unwrap();
+
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let item = Pin::new(&mut stream).poll_next(&mut cx);
+
+        assert!(matches!(
+            item,
+            Poll::Ready(Some(Err(e))) if e.kind() == io::ErrorKind::NotFound
+        ));
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    env,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use crate::{PsiEntry, PsiFd, PsiFdBuilderError, TriggerSpec, TriggerSpecParseError};
+
+/// Errors that can occur when following systemd's memory pressure
+/// protocol
+#[derive(thiserror::Error, Debug)]
+pub enum SystemdPressureError {
+    #[error("MEMORY_PRESSURE_WRITE is not set for {0}")]
+    NoTrigger(PathBuf),
+    #[error("MEMORY_PRESSURE_WRITE is not valid base64")]
+    InvalidEncoding,
+    #[error("MEMORY_PRESSURE_WRITE is not a psi trigger: {0}")]
+    InvalidTrigger(#[from] TriggerSpecParseError),
+    #[error("failed to build trigger: {0}")]
+    Build(#[from] PsiFdBuilderError),
+}
+
+impl PsiFd {
+    /// Registers the memory pressure trigger systemd hands to services.
+    ///
+    /// Since v254, systemd passes the pressure file to watch in
+    /// `$MEMORY_PRESSURE_WATCH` and the base64 encoded trigger to write
+    /// to it in `$MEMORY_PRESSURE_WRITE`, as configured with
+    /// `MemoryPressureWatch=` and `MemoryPressureThresholdSec=`.
+    ///
+    /// Returns `None` if the service is not asked to watch memory
+    /// pressure, i.e. the variable is unset or `/dev/null`. Only PSI files
+    /// are supported, not the FIFOs or sockets the protocol also allows.
+    ///
+    /// ```no_run
+    /// # use presutaoru::*;
+    /// if let Some(psi_fd) = PsiFd::from_systemd_env().unwrap() {
+    ///     // Release caches whenever the trigger fires
+    /// }
+    /// ```
+    pub fn from_systemd_env() -> Result<Option<Self>, SystemdPressureError> {
+        let Some(watch) = env::var_os("MEMORY_PRESSURE_WATCH") else {
+            return Ok(None);
+        };
+        let write = env::var("MEMORY_PRESSURE_WRITE").ok();
+        from_env_values(&watch, write.as_deref())
+    }
+}
+
+fn from_env_values(
+    watch: &OsStr,
+    write: Option<&str>,
+) -> Result<Option<PsiFd>, SystemdPressureError> {
+    let path = Path::new(watch);
+    if path.as_os_str().is_empty() || path == Path::new("/dev/null") {
+        return Ok(None);
+    }
+
+    let write = write.ok_or_else(|| SystemdPressureError::NoTrigger(path.to_path_buf()))?;
+    let decoded = decode_base64(write).ok_or(SystemdPressureError::InvalidEncoding)?;
+    let trigger = String::from_utf8(decoded).map_err(|_| SystemdPressureError::InvalidEncoding)?;
+    // systemd includes the terminating NUL in the encoded trigger
+    let spec: TriggerSpec = trigger.trim_end_matches('\0').parse()?;
+
+    let fd = PsiFd::builder()
+        .entry(PsiEntry::Custom(path))
+        .stall_type(spec.stall_type())
+        .stall_amount(spec.stall_amount())
+        .time_window(spec.time_window())
+        .build()?;
+    Ok(Some(fd))
+}
+
+/// Decodes standard, padded base64.
+fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    fn sextet(c: u8) -> Option<u32> {
+        Some(match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        } as u32)
+    }
+
+    let input = input.trim().as_bytes();
+    if input.len() % 4 != 0 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(input.len() / 4 * 3);
+    for (i, chunk) in input.chunks(4).enumerate() {
+        let last = i == input.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+
+        let mut bits = 0;
+        for &c in &chunk[..4 - padding] {
+            bits = bits << 6 | sextet(c)?;
+        }
+        bits <<= 6 * padding;
+        out.extend_from_slice(&bits.to_be_bytes()[1..4 - padding]);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let cases: [(&str, &[u8]); 4] = [
+            ("", b""),
+            ("Zg==", b"f"),
+            ("Zm8=", b"fo"),
+            ("c29tZSAyMDAwMDAgMjAwMDAwMAA=", b"some 200000 2000000\0"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(decode_base64(input).as_deref(), Some(expected));
+        }
+
+        for input in ["Zg=", "Zg==Zg==", "Z===", "Zm9!"] {
+            assert_eq!(decode_base64(input), None);
+        }
+    }
+
+    #[test]
+    fn env_values() {
+        let watch = OsStr::new("/proc/pressure/memory");
+
+        assert!(
+            from_env_values(OsStr::new("/dev/null"), None)
+                .unwrap()
+                .is_none()
+        );
+        assert_matches!(
+            from_env_values(watch, None),
+            Err(SystemdPressureError::NoTrigger(_))
+        );
+        assert_matches!(
+            from_env_values(watch, Some("bm9wZQ==")),
+            Err(SystemdPressureError::InvalidTrigger(_))
+        );
+
+        let psi_fd = from_env_values(watch, Some("c29tZSAyMDAwMDAgMjAwMDAwMAA="))
+            .unwrap()
+            .unwrap();
+        assert_eq!(psi_fd.trigger().unwrap().to_string(), "some 200000 2000000");
+    }
+}
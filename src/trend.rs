@@ -0,0 +1,180 @@
+use std::{
+    fs::File,
+    io, thread,
+    time::{Duration, Instant},
+};
+
+use crate::{PsiEntry, StallType, stats};
+
+/// Direction of a [`Trend`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrendDirection {
+    Rising,
+    Falling,
+    Steady,
+}
+
+/// Least-squares trend of a stall ratio over time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Trend {
+    /// Change of the stall ratio in percentage points per second.
+    pub slope: f32,
+    /// Coefficient of determination of the fit, from 0 to 1.
+    ///
+    /// Low values mean the samples are too noisy for the slope
+    /// to be meaningful.
+    pub confidence: f32,
+    /// Direction of the slope, see [`Trend::STEADY_SLOPE`].
+    pub direction: TrendDirection,
+}
+
+impl Trend {
+    /// Slopes within this many percentage points per second
+    /// of zero are considered [`TrendDirection::Steady`].
+    pub const STEADY_SLOPE: f32 = 0.05;
+
+    const SAMPLES: u32 = 10;
+
+    /// Samples the stall ratio of `entry` over `window` and fits a trend.
+    ///
+    /// This blocks for `window`. The ratio is derived from the growth of
+    /// the `total` field between evenly spaced reads, so it reflects the
+    /// window itself rather than the kernel's running averages.
+    pub fn of(entry: PsiEntry<'_>, stall_type: StallType, window: Duration) -> io::Result<Self> {
+        let file = File::open(entry.path())?;
+        let interval = window / Self::SAMPLES;
+
+        let start = Instant::now();
+        let mut samples = Vec::with_capacity(Self::SAMPLES as usize);
+        let mut previous = (start, total(&file, stall_type)?);
+        for _ in 0..Self::SAMPLES {
+            thread::sleep(interval);
+
+            let now = (Instant::now(), total(&file, stall_type)?);
+            let elapsed = now.0 - previous.0;
+            let stalled = now.1.saturating_sub(previous.1);
+            let ratio = stalled.as_secs_f32() / elapsed.as_secs_f32() * 100.0;
+            samples.push((now.0 - start, ratio));
+            previous = now;
+        }
+
+        Self::fit(&samples).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "window too small to sample")
+        })
+    }
+
+    /// Fits a trend to `(elapsed, stall ratio in percent)` samples.
+    ///
+    /// Returns `None` with fewer than two distinct points in time.
+    pub fn fit(samples: &[(Duration, f32)]) -> Option<Self> {
+        if samples.len() < 2 {
+            return None;
+        }
+
+        let n = samples.len() as f64;
+        let points = samples
+            .iter()
+            .map(|&(t, ratio)| (t.as_secs_f64(), ratio as f64));
+
+        let (sum_t, sum_r) = points
+            .clone()
+            .fold((0.0, 0.0), |(st, sr), (t, r)| (st + t, sr + r));
+        let (mean_t, mean_r) = (sum_t / n, sum_r / n);
+
+        let (mut var_t, mut cov, mut var_r) = (0.0, 0.0, 0.0);
+        for (t, r) in points {
+            let (dt, dr) = (t - mean_t, r - mean_r);
+            var_t += dt * dt;
+            cov += dt * dr;
+            var_r += dr * dr;
+        }
+        if var_t == 0.0 {
+            return None;
+        }
+
+        let slope = (cov / var_t) as f32;
+        let confidence = if var_r == 0.0 {
+            1.0
+        } else {
+            (cov * cov / (var_t * var_r)) as f32
+        };
+        let direction = if slope > Self::STEADY_SLOPE {
+            TrendDirection::Rising
+        } else if slope < -Self::STEADY_SLOPE {
+            TrendDirection::Falling
+        } else {
+            TrendDirection::Steady
+        };
+
+        Some(Self {
+            slope,
+            confidence,
+            direction,
+        })
+    }
+}
+
+fn total(file: &File, stall_type: StallType) -> io::Result<Duration> {
+    stats::read_from(file)?
+        .get(stall_type)
+        .map(|stats| stats.total)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("entry does not report {stall_type} pressure"),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(ratios: &[f32]) -> Vec<(Duration, f32)> {
+        ratios
+            .iter()
+            .enumerate()
+            .map(|(i, &ratio)| (Duration::from_secs(i as u64), ratio))
+            .collect()
+    }
+
+    #[test]
+    fn fit_rising() {
+        let trend = Trend::fit(&samples(&[1.0, 2.0, 3.0, 4.0])).unwrap();
+
+        assert!((trend.slope - 1.0).abs() < 1e-6);
+        assert!((trend.confidence - 1.0).abs() < 1e-6);
+        assert_eq!(trend.direction, TrendDirection::Rising);
+    }
+
+    #[test]
+    fn fit_falling() {
+        let trend = Trend::fit(&samples(&[8.0, 6.0, 4.0, 2.0])).unwrap();
+
+        assert!((trend.slope + 2.0).abs() < 1e-6);
+        assert_eq!(trend.direction, TrendDirection::Falling);
+    }
+
+    #[test]
+    fn fit_steady() {
+        let trend = Trend::fit(&samples(&[3.0, 3.0, 3.0])).unwrap();
+
+        assert_eq!(trend.slope, 0.0);
+        assert_eq!(trend.confidence, 1.0);
+        assert_eq!(trend.direction, TrendDirection::Steady);
+    }
+
+    #[test]
+    fn fit_noisy_has_low_confidence() {
+        let trend = Trend::fit(&samples(&[0.0, 10.0, 0.0, 10.0, 0.0, 10.0, 0.0])).unwrap();
+
+        assert!(trend.confidence < 0.1);
+    }
+
+    #[test]
+    fn fit_requires_two_points_in_time() {
+        assert!(Trend::fit(&[]).is_none());
+        assert!(Trend::fit(&samples(&[1.0])).is_none());
+        assert!(Trend::fit(&[(Duration::ZERO, 1.0), (Duration::ZERO, 2.0)]).is_none());
+    }
+}
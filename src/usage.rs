@@ -0,0 +1,131 @@
+use std::{fs, io};
+
+use crate::fd::is_psi_path;
+
+/// Trigger usage of the current process.
+///
+/// The kernel has no dedicated limit on the number of triggers. Every
+/// trigger holds an open PSI file, so the limit that applies is the
+/// process' open file limit (`RLIMIT_NOFILE`). When it is reached,
+/// [`PsiFdBuilder::build`](crate::PsiFdBuilder::build) fails with
+/// [`PsiFdBuilderError::TriggerLimitReached`](crate::PsiFdBuilderError::TriggerLimitReached).
+///
+/// ```no_run
+/// # use presutaoru::*;
+/// let usage = TriggerUsage::current().unwrap();
+/// eprintln!(
+///     "{} triggers open, room for {:?} more",
+///     usage.psi_fds,
+///     usage.remaining()
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TriggerUsage {
+    /// Open fds referring to a PSI file.
+    ///
+    /// This includes fds not created through this crate, and PSI files
+    /// opened for reading only.
+    pub psi_fds: usize,
+    /// All open fds.
+    pub fds: usize,
+    /// Soft limit on open fds, `None` if unlimited.
+    pub fd_limit: Option<u64>,
+}
+
+impl TriggerUsage {
+    const LIMITS: &'static str = "/proc/self/limits";
+    const FDS: &'static str = "/proc/self/fd";
+
+    /// Inspects the fds of the current process.
+    pub fn current() -> io::Result<Self> {
+        let fd_limit = parse_fd_limit(&fs::read_to_string(Self::LIMITS)?)?;
+
+        let (mut fds, mut psi_fds) = (0, 0);
+        for fd in fs::read_dir(Self::FDS)? {
+            // The fd of the directory itself may be gone by the time it is read
+            let Ok(target) = fs::read_link(fd?.path()) else {
+                continue;
+            };
+            fds += 1;
+            if is_psi_path(&target) {
+                psi_fds += 1;
+            }
+        }
+
+        Ok(Self {
+            psi_fds,
+            fds,
+            fd_limit,
+        })
+    }
+
+    /// Returns how many more triggers can be created before the open file
+    /// limit is reached, `None` if unlimited.
+    pub fn remaining(&self) -> Option<u64> {
+        self.fd_limit
+            .map(|limit| limit.saturating_sub(self.fds as u64))
+    }
+}
+
+/// Returns the soft limit of the `Max open files` line.
+///
+/// ```console
+/// Limit                     Soft Limit           Hard Limit           Units
+/// Max open files            1024                 524288               files
+/// ```
+fn parse_fd_limit(limits: &str) -> io::Result<Option<u64>> {
+    let soft = limits
+        .lines()
+        .find_map(|line| line.strip_prefix("Max open files"))
+        .and_then(|rest| rest.split_ascii_whitespace().next());
+
+    match soft {
+        Some("unlimited") => Ok(None),
+        Some(soft) => soft
+            .parse()
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "no open file limit in /proc/self/limits",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    #[test]
+    fn parse_limits() {
+        let limits = "\
+Limit                     Soft Limit           Hard Limit           Units
+Max processes             63304                63304                processes
+Max open files            1024                 524288               files
+";
+
+        assert_eq!(parse_fd_limit(limits).unwrap(), Some(1024));
+        assert_eq!(
+            parse_fd_limit(
+                "Max open files            unlimited            unlimited            files"
+            )
+            .unwrap(),
+            None
+        );
+        assert!(parse_fd_limit("Max processes 1 1 processes").is_err());
+    }
+
+    #[test]
+    fn counts_psi_fds() {
+        let before = TriggerUsage::current().unwrap();
+        let _file = File::open("/proc/pressure/cpu").unwrap();
+        let after = TriggerUsage::current().unwrap();
+
+        // Other tests may open and close fds concurrently
+        assert!(after.psi_fds >= 1);
+        assert!(after.fds >= after.psi_fds);
+        assert_eq!(after.fd_limit, before.fd_limit);
+    }
+}
@@ -40,3 +40,101 @@ async fn integration() {
         other => panic!("{other:?}"),
     }
 }
+
+#[test]
+fn read_stats() {
+    let psi_fd = PsiFd::builder()
+        .entry(PsiEntry::Global(GlobalEntryType::Memory))
+        .stall_amount(Duration::from_micros(1))
+        .stall_type(StallType::Some)
+        .time_window(Duration::from_secs(2))
+        .build()
+        .unwrap();
+
+    let stats = psi_fd.read_stats().unwrap();
+    assert!(stats.some.is_some());
+
+    // Repeated reads start from the beginning of the file
+    assert!(psi_fd.read_stats().unwrap().some.is_some());
+}
+
+#[test]
+fn remembers_trigger() {
+    let psi_fd = PsiFd::builder()
+        .entry(PsiEntry::Global(GlobalEntryType::Memory))
+        .stall_amount(Duration::from_millis(150))
+        .stall_type(StallType::Full)
+        .time_window(Duration::from_secs(2))
+        .build()
+        .unwrap();
+
+    assert_eq!(psi_fd.trigger().unwrap().to_string(), "full 150000 2000000");
+    assert_eq!(
+        psi_fd.path().unwrap(),
+        PsiEntry::Global(GlobalEntryType::Memory).path()
+    );
+}
+
+#[test]
+fn follow_pid() {
+    let spec = TriggerSpec::new(
+        StallType::Some,
+        Duration::from_micros(1),
+        Duration::from_secs(2),
+    )
+    .unwrap();
+
+    let mut psi_fd = PidPsiFd::new(std::process::id(), CgroupEntryType::Memory, spec).unwrap();
+
+    assert!(psi_fd.refresh().unwrap().is_none());
+    assert!(psi_fd.fd().read_stats().unwrap().some.is_some());
+}
+
+#[test]
+fn watch_own_cgroup() {
+    let spec: TriggerSpec = "some 1 2000000".parse().unwrap();
+
+    let psi_fd = Cgroup::current()
+        .unwrap()
+        .trigger(CgroupEntryType::Memory, spec)
+        .unwrap();
+
+    assert_eq!(psi_fd.trigger(), Some(spec));
+    assert!(psi_fd.read_stats().unwrap().some.is_some());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn removed_cgroup_releases_trigger() {
+    let child = Cgroup::current()
+        .unwrap()
+        .path()
+        .join(format!("presutaoru-test-{}", std::process::id()));
+    if let Err(e) = std::fs::create_dir(&child) {
+        eprintln!("skipping, cannot create {}: {e}", child.display());
+        return;
+    }
+
+    let async_fd = PsiFd::builder()
+        .entry(PsiEntry::Cgroup(CgroupEntryType::Memory, &child))
+        .stall_type(StallType::Some)
+        .stall_amount(Duration::from_micros(1))
+        .time_window(Duration::from_secs(2))
+        .build()
+        .map(PsiAsyncFd::new);
+    // Remove the cgroup before unwrapping so that a failure does not leak it
+    std::fs::remove_dir(&child).unwrap();
+    let async_fd = async_fd.unwrap().unwrap();
+
+    let released = async {
+        loop {
+            if let Err(e) = async_fd.next_event().await {
+                return e;
+            }
+        }
+    };
+    let e = time::timeout(Duration::from_secs(2), released)
+        .await
+        .unwrap();
+    assert_eq!(e.kind(), std::io::ErrorKind::NotFound);
+}